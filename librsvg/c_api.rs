@@ -33,7 +33,7 @@ use gobject_sys::{GEnumValue, GFlagsValue};
 
 use rsvg_internals::{
     rsvg_log, DefsLookupErrorKind, Handle, IntrinsicDimensions, LoadOptions, LoadingError,
-    RenderingError, RsvgLength, SharedImageSurface, SurfaceType, ViewBox,
+    RenderingError, RenderingOptions, RsvgLength, SharedImageSurface, SurfaceType, ViewBox,
 };
 
 use crate::dpi::Dpi;
@@ -251,6 +251,15 @@ struct CHandleInner {
     is_testing: bool,
 }
 
+impl CHandleInner {
+    fn rendering_options(&self) -> RenderingOptions {
+        RenderingOptions {
+            testing: self.is_testing,
+            ..Default::default()
+        }
+    }
+}
+
 unsafe impl ClassStruct for RsvgHandleClass {
     type Type = CHandle;
 }
@@ -930,7 +939,7 @@ impl CHandle {
 
         let handle = self.get_handle_ref()?;
         let inner = self.inner.borrow();
-        handle.render_document(cr, viewport, inner.dpi.into(), inner.is_testing)
+        handle.render_document(cr, viewport, inner.dpi.into(), inner.rendering_options())
     }
 
     fn get_geometry_for_layer(
@@ -957,7 +966,13 @@ impl CHandle {
         let handle = self.get_handle_ref()?;
         let inner = self.inner.borrow();
         handle
-            .render_layer(cr, id, viewport, inner.dpi.into(), inner.is_testing)
+            .render_layer(
+                cr,
+                id,
+                viewport,
+                inner.dpi.into(),
+                inner.rendering_options(),
+            )
             .map_err(warn_on_invalid_id)
     }
 
@@ -984,7 +999,13 @@ impl CHandle {
         let handle = self.get_handle_ref()?;
         let inner = self.inner.borrow();
         handle
-            .render_element(cr, id, element_viewport, inner.dpi.into(), inner.is_testing)
+            .render_element(
+                cr,
+                id,
+                element_viewport,
+                inner.dpi.into(),
+                inner.rendering_options(),
+            )
             .map_err(warn_on_invalid_id)
     }
 
@@ -993,6 +1014,23 @@ impl CHandle {
         Ok(handle.get_intrinsic_dimensions())
     }
 
+    fn get_diagnostics(&self) -> Result<Option<String>, RenderingError> {
+        let handle = self.get_handle_ref()?;
+        let diagnostics = handle.get_diagnostics();
+
+        if diagnostics.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(
+                diagnostics
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ))
+        }
+    }
+
     fn set_testing(&self, is_testing: bool) {
         let mut inner = self.inner.borrow_mut();
         inner.is_testing = is_testing;
@@ -1683,6 +1721,24 @@ pub unsafe extern "C" fn rsvg_rust_handle_get_intrinsic_dimensions(
     set_out_param(out_has_viewbox, out_viewbox, &r);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn rsvg_rust_handle_get_diagnostics(
+    handle: *const RsvgHandle,
+) -> *mut libc::c_char {
+    rsvg_return_val_if_fail! {
+        rsvg_handle_get_diagnostics => ptr::null_mut();
+
+        is_rsvg_handle(handle),
+    }
+
+    let rhandle = get_rust_handle(handle);
+
+    match rhandle.get_diagnostics() {
+        Ok(diagnostics) => diagnostics.to_glib_full(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn rsvg_rust_handle_render_document(
     handle: *const RsvgHandle,
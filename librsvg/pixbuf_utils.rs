@@ -10,7 +10,8 @@ use url::Url;
 use crate::c_api::checked_i32;
 
 use rsvg_internals::{
-    Dpi, Handle, LoadOptions, LoadingError, Pixel, RenderingError, SharedImageSurface, SurfaceType,
+    Dpi, Handle, LoadOptions, LoadingError, Pixel, RenderingError, RenderingOptions,
+    SharedImageSurface, SurfaceType,
 };
 
 use crate::c_api::set_gerror;
@@ -188,7 +189,7 @@ fn render_to_pixbuf_at_size(
         };
 
         // We do it with a cr transform so we can scale non-proportionally.
-        handle.render_document(&cr, &viewport, dpi, false)?;
+        handle.render_document(&cr, &viewport, dpi, RenderingOptions::default())?;
     }
 
     let shared_surface = SharedImageSurface::wrap(surface, SurfaceType::SRgb)?;
@@ -378,6 +378,7 @@ extern void rsvg_rust_handle_get_intrinsic_dimensions (RsvgHandle *handle,
                                                        RsvgLength *out_height,
                                                        gboolean   *out_has_viewbox,
                                                        RsvgRectangle *out_viewbox);
+extern char *rsvg_rust_handle_get_diagnostics (RsvgHandle *handle);
 extern gboolean rsvg_rust_handle_render_document (RsvgHandle           *handle,
                                                   cairo_t              *cr,
                                                   const RsvgRectangle  *viewport,
@@ -1209,6 +1210,31 @@ rsvg_handle_get_intrinsic_dimensions (RsvgHandle *handle,
                                                out_viewbox);
 }
 
+/**
+ * rsvg_handle_get_diagnostics:
+ * @handle: An #RsvgHandle
+ *
+ * Gets the attribute errors that were found while loading the SVG document.
+ *
+ * When an element has an invalid attribute, for example a <literal>transform</literal>
+ * that cannot be parsed, the element is not rendered.  This function lets applications
+ * report why.  Each error is on a separate line, and mentions the element's name and the
+ * offending attribute.
+ *
+ * API ordering: This function must be called on a fully-loaded @handle.  See
+ * the section <ulink url="#API-ordering">API ordering</ulink> for details.
+ *
+ * Returns: (nullable) (transfer full): a newly-allocated string with the errors, or
+ * #NULL if there were none.  Free it with g_free().
+ *
+ * Since: 2.50
+ */
+char *
+rsvg_handle_get_diagnostics (RsvgHandle *handle)
+{
+    return rsvg_rust_handle_get_diagnostics (handle);
+}
+
 /**
  * rsvg_handle_render_document:
  * @handle: An #RsvgHandle
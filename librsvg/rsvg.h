@@ -228,6 +228,8 @@ gboolean rsvg_handle_has_sub (RsvgHandle *handle, const char *id);
  * @RSVG_UNIT_MM: millimeters
  * @RSVG_UNIT_PT: points, or 1/72 inch
  * @RSVG_UNIT_PC: picas, or 1/6 inch (12 points)
+ * @RSVG_UNIT_CH: advance width of the "0" glyph in the current font; librsvg
+ *   approximates it as half the font size.  Since 2.50.
  *
  * Units for the #RsvgLength struct.  These have the same meaning as <ulink
  * url="https://www.w3.org/TR/CSS21/syndata.html#length-units">CSS length
@@ -242,7 +244,8 @@ typedef enum {
     RSVG_UNIT_CM,
     RSVG_UNIT_MM,
     RSVG_UNIT_PT,
-    RSVG_UNIT_PC
+    RSVG_UNIT_PC,
+    RSVG_UNIT_CH
 } RsvgUnit;
 
 /**
@@ -278,6 +281,9 @@ void rsvg_handle_get_intrinsic_dimensions (RsvgHandle *handle,
                                            gboolean   *out_has_viewbox,
                                            RsvgRectangle *out_viewbox);
 
+RSVG_API
+char *rsvg_handle_get_diagnostics (RsvgHandle *handle);
+
 /* GIO APIs */
 
 /**
@@ -157,7 +157,7 @@ use std::path::Path;
 
 use gio::{Cancellable, FileExt};
 
-use rsvg_internals::{Dpi, Handle, LoadOptions};
+use rsvg_internals::{Dpi, Handle, LoadOptions, RenderingOptions};
 
 pub use rsvg_internals::{
     DefsLookupErrorKind, HrefError, Length as InternalLength, LengthUnit, LoadingError,
@@ -388,12 +388,18 @@ impl SvgHandle {
 pub struct CairoRenderer<'a> {
     handle: &'a SvgHandle,
     dpi: Dpi,
+    options: RenderingOptions,
 }
 
 // Note that these are different than the C API's default, which is 90.
 const DEFAULT_DPI_X: f64 = 96.0;
 const DEFAULT_DPI_Y: f64 = 96.0;
 
+/// The smallest scale that [`CairoRenderer::with_filter_scale`] accepts.
+///
+/// [`CairoRenderer::with_filter_scale`]: struct.CairoRenderer.html#method.with_filter_scale
+pub const MIN_FILTER_SCALE: f64 = 0.01;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 /// Contains the values of the `<svg>` element's `width`, `height`, and `viewBox` attributes.
 ///
@@ -432,6 +438,7 @@ impl<'a> CairoRenderer<'a> {
         CairoRenderer {
             handle,
             dpi: Dpi::new(DEFAULT_DPI_X, DEFAULT_DPI_Y),
+            options: RenderingOptions::default(),
         }
     }
 
@@ -445,8 +452,54 @@ impl<'a> CairoRenderer<'a> {
         assert!(dpi_y > 0.0);
 
         CairoRenderer {
-            handle: self.handle,
             dpi: Dpi::new(dpi_x, dpi_y),
+            ..self
+        }
+    }
+
+    /// Configures whether filters should always operate in sRGB.
+    ///
+    /// By default, filter primitives honor the `color-interpolation-filters` property,
+    /// which makes most of them convert their inputs to linear RGB and their results
+    /// back to sRGB.  Passing `true` here skips those conversions, which makes filters
+    /// faster but changes their results: blurs and color blending will look different
+    /// from what the SVG specification requires.
+    pub fn with_srgb_filters(self, srgb_filters: bool) -> Self {
+        CairoRenderer {
+            options: RenderingOptions {
+                srgb_filters,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Configures the resolution at which filters are rendered.
+    ///
+    /// The `scale` is relative to the output resolution.  The default is 1.0.  With a
+    /// smaller value, for example 0.5, the contents of filtered elements are scaled down
+    /// before running the filter primitives, and the results are scaled back up.  This
+    /// makes expensive filters like blurs and lighting much cheaper, at the cost of blurrier
+    /// output, which is useful for previews.
+    ///
+    /// Values larger than 1.0 are clamped to 1.0, and values smaller than
+    /// [`MIN_FILTER_SCALE`] to that minimum.  A `scale` that is not a number is ignored,
+    /// and filters are rendered at full resolution.
+    ///
+    /// [`MIN_FILTER_SCALE`]: constant.MIN_FILTER_SCALE.html
+    pub fn with_filter_scale(self, scale: f64) -> Self {
+        let filter_scale = if scale.is_nan() {
+            1.0
+        } else {
+            scale.max(MIN_FILTER_SCALE).min(1.0)
+        };
+
+        CairoRenderer {
+            options: RenderingOptions {
+                filter_scale,
+                ..self.options
+            },
+            ..self
         }
     }
 
@@ -480,7 +533,9 @@ impl<'a> CairoRenderer<'a> {
         cr: &cairo::Context,
         viewport: &cairo::Rectangle,
     ) -> Result<(), RenderingError> {
-        self.handle.0.render_document(cr, viewport, self.dpi, false)
+        self.handle
+            .0
+            .render_document(cr, viewport, self.dpi, self.options)
     }
 
     /// Computes the (ink_rect, logical_rect) of an SVG element, as if
@@ -545,7 +600,7 @@ impl<'a> CairoRenderer<'a> {
     ) -> Result<(), RenderingError> {
         self.handle
             .0
-            .render_layer(cr, id, viewport, self.dpi, false)
+            .render_layer(cr, id, viewport, self.dpi, self.options)
     }
 
     /// Computes the (ink_rect, logical_rect) of a single SVG element
@@ -612,6 +667,6 @@ impl<'a> CairoRenderer<'a> {
     ) -> Result<(), RenderingError> {
         self.handle
             .0
-            .render_element(cr, id, element_viewport, self.dpi, false)
+            .render_element(cr, id, element_viewport, self.dpi, self.options)
     }
 }
@@ -222,3 +222,32 @@ fn set_stylesheet() {
 
     compare_to_surface(&output_surf, &reference_surf, "set_stylesheet");
 }
+
+#[test]
+fn out_of_range_filter_scale_is_clamped() {
+    let svg = load_svg(
+        br##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <filter id="filter">
+    <feGaussianBlur stdDeviation="5"/>
+  </filter>
+  <rect x="10" y="10" width="80" height="80" fill="#0000ff" filter="url(#filter)"/>
+</svg>
+"##,
+    );
+
+    let viewport = cairo::Rectangle {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+
+    for &scale in &[std::f64::NAN, -1.0, 0.0, 2.0] {
+        let output = cairo::ImageSurface::create(cairo::Format::ARgb32, 100, 100).unwrap();
+        let cr = cairo::Context::new(&output);
+
+        let renderer = CairoRenderer::new(&svg).with_filter_scale(scale);
+        assert!(renderer.render_document(&cr, &viewport).is_ok());
+    }
+}
@@ -40,5 +40,87 @@ fn bench_composite(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_composite);
+fn bench_composite_arithmetic_large(c: &mut Criterion) {
+    const SIDE: i32 = 1000;
+
+    let surface_with_data = |seed: u8| {
+        let mut surface = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+
+        for (i, row) in surface.rows_mut().enumerate() {
+            for (j, p) in row.iter_mut().enumerate() {
+                p.a = (i + j) as u8 | seed;
+                p.r = (i as u8).min(p.a);
+                p.g = (j as u8).min(p.a);
+                p.b = seed.min(p.a);
+            }
+        }
+
+        surface.share().unwrap()
+    };
+
+    let input_surface = surface_with_data(0x40);
+    let input_2_surface = surface_with_data(0x81);
+
+    let mut output_surface = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+
+    let full_bounds = IRect::from_size(SIDE, SIDE);
+
+    c.bench_function("composite arithmetic 1000x1000", |b| {
+        let bounds = black_box(full_bounds);
+
+        b.iter(|| {
+            composite_arithmetic(
+                &input_surface,
+                &input_2_surface,
+                &mut output_surface,
+                bounds,
+                0.5,
+                0.25,
+                0.5,
+                0.0,
+            );
+        })
+    });
+}
+
+fn bench_composite_in(c: &mut Criterion) {
+    let input_surface =
+        SharedImageSurface::empty(SURFACE_SIDE, SURFACE_SIDE, SurfaceType::SRgb).unwrap();
+    let input_2_surface =
+        SharedImageSurface::empty(SURFACE_SIDE, SURFACE_SIDE, SurfaceType::SRgb).unwrap();
+
+    let full_bounds = IRect::from_size(SURFACE_SIDE, SURFACE_SIDE);
+
+    c.bench_function("composite in", |b| {
+        let bounds = black_box(full_bounds);
+
+        b.iter(|| {
+            input_surface
+                .compose(&input_2_surface, bounds, cairo::Operator::In)
+                .unwrap()
+        })
+    });
+
+    c.bench_function("composite in with linearization", |b| {
+        let bounds = black_box(full_bounds);
+
+        b.iter(|| {
+            let input = input_surface.to_linear_rgb(bounds).unwrap();
+            let input_2 = input_2_surface.to_linear_rgb(bounds).unwrap();
+
+            input
+                .compose(&input_2, bounds, cairo::Operator::In)
+                .unwrap()
+                .to_srgb(bounds)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_composite,
+    bench_composite_arithmetic_large,
+    bench_composite_in
+);
 criterion_main!(benches);
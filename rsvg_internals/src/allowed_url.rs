@@ -168,7 +168,7 @@ pub enum Href {
 }
 
 /// Optional URI, mandatory fragment id
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Fragment(Option<String>, String);
 
 impl Fragment {
@@ -193,6 +193,14 @@ impl Fragment {
     pub fn fragment(&self) -> &str {
         &self.1
     }
+
+    /// Returns a string that identifies this fragment, for use as a cache key.
+    ///
+    /// This is `uri#fragment`, or `#fragment` if there is no URI.  It is unambiguous
+    /// because URIs are never empty and fragment ids never contain a `#`.
+    pub fn cache_key(&self) -> String {
+        format!("{}#{}", self.uri().unwrap_or(""), self.fragment())
+    }
 }
 
 impl fmt::Display for Fragment {
@@ -352,6 +360,14 @@ mod tests {
             ))
         );
 
+        assert_eq!(
+            Href::parse("uri?v=2#fragment").unwrap(),
+            Href::WithFragment(Fragment::new(
+                Some("uri?v=2".to_string()),
+                "fragment".to_string()
+            ))
+        );
+
         assert_eq!(Href::parse(""), Err(HrefError::ParseError));
         assert_eq!(Href::parse("#"), Err(HrefError::ParseError));
         assert_eq!(Href::parse("uri#"), Err(HrefError::ParseError));
@@ -371,4 +387,27 @@ mod tests {
 
         assert_eq!(Fragment::parse("uri"), Err(HrefError::FragmentRequired));
     }
+
+    #[test]
+    fn equal_fragments_have_equal_keys_and_hashes() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |f: &Fragment| {
+            let mut hasher = DefaultHasher::new();
+            f.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let a = Fragment::parse("uri#foo").unwrap();
+        let b = Fragment::new(Some("uri".to_string()), "foo".to_string());
+        assert_eq!(a, b);
+        assert_eq!(a.cache_key(), "uri#foo");
+        assert_eq!(a.cache_key(), b.cache_key());
+        assert_eq!(hash(&a), hash(&b));
+
+        let c = Fragment::parse("#foo").unwrap();
+        assert_eq!(c.cache_key(), "#foo");
+        assert_ne!(a.cache_key(), c.cache_key());
+    }
 }
@@ -459,6 +459,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transform_for_default_meets() {
+        let a = AspectRatio::default();
+        let t = a.viewport_to_viewbox_transform(
+            Some(ViewBox::parse_str("0 0 10 20").unwrap()),
+            &Rect::from_size(100.0, 100.0),
+        );
+
+        assert_eq!(
+            t,
+            Ok(Some(
+                Transform::new_translate(25.0, 0.0).pre_scale(5.0, 5.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn transform_for_none_scales_non_uniformly() {
+        let a = AspectRatio::parse_str("none").unwrap();
+        let t = a.viewport_to_viewbox_transform(
+            Some(ViewBox::parse_str("0 0 10 20").unwrap()),
+            &Rect::from_size(100.0, 100.0),
+        );
+
+        assert_eq!(t, Ok(Some(Transform::new_scale(10.0, 5.0))));
+    }
+
+    #[test]
+    fn transform_for_slice() {
+        let a = AspectRatio::parse_str("xMidYMax slice").unwrap();
+        let t = a.viewport_to_viewbox_transform(
+            Some(ViewBox::parse_str("0 0 10 20").unwrap()),
+            &Rect::from_size(100.0, 100.0),
+        );
+
+        assert_eq!(
+            t,
+            Ok(Some(
+                Transform::new_translate(0.0, -100.0).pre_scale(10.0, 10.0)
+            ))
+        );
+    }
+
     #[test]
     fn invalid_viewbox() {
         let a = AspectRatio::default();
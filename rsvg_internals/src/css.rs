@@ -760,6 +760,8 @@ pub fn cascade(
             s.get_matches(&node, &mut match_ctx, &mut matches);
         }
 
+        // This is a stable sort, so matches with the same origin and specificity
+        // stay in document order, and later declarations override earlier ones.
         matches.as_mut_slice().sort();
 
         for m in matches {
@@ -777,25 +779,12 @@ pub fn cascade(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use gio;
-    use glib::{self, prelude::*};
     use selectors::Element;
 
     use crate::allowed_url::Fragment;
-    use crate::document::Document;
-    use crate::handle::LoadOptions;
-
-    fn load_document(input: &'static [u8]) -> Document {
-        let bytes = glib::Bytes::from_static(input);
-        let stream = gio::MemoryInputStream::new_from_bytes(&bytes);
-
-        Document::load_from_stream(
-            &LoadOptions::new(None),
-            &stream.upcast(),
-            None::<&gio::Cancellable>,
-        )
-        .unwrap()
-    }
+    use crate::document::load_document;
+    use crate::length::*;
+    use crate::property_defs::StrokeWidth;
 
     #[test]
     fn impl_element() {
@@ -880,4 +869,40 @@ mod tests {
         assert!(d.is_empty());
         assert!(!a.is_empty());
     }
+
+    #[test]
+    fn later_style_elements_override_earlier_ones() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <style>rect { stroke-width: 1px; } .foo { stroke-width: 5px; }</style>
+  <style>rect { stroke-width: 2px; }</style>
+  <rect id="a"/>
+  <rect id="b" class="foo"/>
+  <style>rect { stroke-width: 3px; }</style>
+</svg>
+"#,
+        );
+
+        let stroke_width = |id| {
+            document
+                .lookup_node_by_id(id)
+                .unwrap()
+                .borrow_element()
+                .get_computed_values()
+                .stroke_width()
+        };
+
+        // Same specificity: the last <style> in the document wins
+        assert_eq!(
+            stroke_width("a"),
+            StrokeWidth(Length::<Both>::new(3.0, LengthUnit::Px))
+        );
+
+        // A more specific selector wins regardless of document order
+        assert_eq!(
+            stroke_width("b"),
+            StrokeWidth(Length::<Both>::new(5.0, LengthUnit::Px))
+        );
+    }
 }
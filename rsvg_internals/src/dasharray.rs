@@ -51,6 +51,10 @@ impl Parse for Dasharray {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dpi::Dpi;
+    use crate::drawing_ctx::ViewParams;
+    use crate::float_eq_cairo::ApproxEqCairo;
+    use crate::properties::ComputedValues;
 
     #[test]
     fn parses_dash_array() {
@@ -114,4 +118,21 @@ mod tests {
         // A comma should be followed by a number
         assert!(Dasharray::parse_str("20,,10").is_err());
     }
+
+    #[test]
+    fn percentages_resolve_against_viewport_diagonal() {
+        let dashes = match Dasharray::parse_str("10% 20%").unwrap() {
+            Dasharray::Array(dashes) => dashes,
+            Dasharray::None => unreachable!(),
+        };
+
+        let params = ViewParams::new(Dpi::new(96.0, 96.0), 300.0, 400.0);
+        let values = ComputedValues::default();
+
+        // sqrt(300^2 + 400^2) / sqrt(2)
+        let diagonal = 500.0 / 2.0f64.sqrt();
+
+        assert_approx_eq_cairo!(dashes[0].normalize(&values, &params), diagonal * 0.1);
+        assert_approx_eq_cairo!(dashes[1].normalize(&values, &params), diagonal * 0.2);
+    }
 }
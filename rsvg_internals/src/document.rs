@@ -8,10 +8,11 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::include_str;
 use std::rc::Rc;
+use url::Url;
 
 use crate::allowed_url::{AllowedUrl, AllowedUrlError, Fragment};
 use crate::css::{self, Origin, Stylesheet};
-use crate::error::{AcquireError, LoadingError};
+use crate::error::{AcquireError, LoadingError, ProcessingError};
 use crate::handle::LoadOptions;
 use crate::io::{self, BinaryData};
 use crate::limits;
@@ -46,6 +47,9 @@ pub struct Document {
 
     /// Stylesheets defined in the document
     stylesheets: Vec<Stylesheet>,
+
+    /// Attribute errors found while loading the document
+    diagnostics: Vec<ProcessingError>,
 }
 
 impl Document {
@@ -58,6 +62,7 @@ impl Document {
         xml_load_from_possibly_compressed_stream(
             DocumentBuilder::new(load_options),
             load_options.unlimited_size,
+            load_options.strict_elements,
             stream,
             cancellable,
         )
@@ -88,6 +93,11 @@ impl Document {
         self.ids.get(id).map(|n| (*n).clone())
     }
 
+    /// Returns the attribute errors that were found while loading the document.
+    pub fn diagnostics(&self) -> &[ProcessingError] {
+        &self.diagnostics
+    }
+
     /// Loads an image by URL, or returns a pre-loaded one.
     pub fn lookup_image(&self, href: &str) -> Result<SharedImageSurface, LoadingError> {
         let aurl = AllowedUrl::from_href(href, self.load_options.base_url.as_ref())
@@ -105,6 +115,28 @@ impl Document {
     }
 }
 
+/// Loads a document from SVG data in memory, for unit tests.
+///
+/// Panics if the document cannot be loaded.
+#[cfg(test)]
+pub fn load_document(input: &'static [u8]) -> Document {
+    load_document_with_options(input, &LoadOptions::new(None)).unwrap()
+}
+
+/// Loads a document from SVG data in memory with the given options, for unit tests.
+#[cfg(test)]
+pub fn load_document_with_options(
+    input: &'static [u8],
+    load_options: &LoadOptions,
+) -> Result<Document, LoadingError> {
+    use glib::prelude::*;
+
+    let bytes = glib::Bytes::from_static(input);
+    let stream = gio::MemoryInputStream::new_from_bytes(&bytes);
+
+    Document::load_from_stream(load_options, &stream.upcast(), None::<&gio::Cancellable>)
+}
+
 struct Resources {
     resources: HashMap<AllowedUrl, Result<Rc<Document>, LoadingError>>,
 }
@@ -358,6 +390,7 @@ pub struct DocumentBuilder {
     tree: Option<Node>,
     ids: HashMap<String, Node>,
     stylesheets: Vec<Stylesheet>,
+    diagnostics: Vec<ProcessingError>,
 }
 
 impl DocumentBuilder {
@@ -367,6 +400,7 @@ impl DocumentBuilder {
             tree: None,
             ids: HashMap::new(),
             stylesheets: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
@@ -400,6 +434,13 @@ impl DocumentBuilder {
     ) -> Node {
         let node = Node::new(NodeData::new_element(name, pbag));
 
+        if let Some(error) = node.borrow_element().get_error() {
+            self.diagnostics.push(ProcessingError {
+                element_name: name.clone(),
+                error: error.clone(),
+            });
+        }
+
         if let Some(id) = node.borrow_element().get_id() {
             // This is so we don't overwrite an existing id
             self.ids
@@ -443,6 +484,10 @@ impl DocumentBuilder {
         };
     }
 
+    pub fn base_url(&self) -> Option<&Url> {
+        self.load_options.base_url.as_ref()
+    }
+
     pub fn resolve_href(&self, href: &str) -> Result<AllowedUrl, AllowedUrlError> {
         AllowedUrl::from_href(href, self.load_options.base_url.as_ref())
     }
@@ -453,6 +498,7 @@ impl DocumentBuilder {
             tree,
             ids,
             stylesheets,
+            diagnostics,
             ..
         } = self;
 
@@ -466,6 +512,7 @@ impl DocumentBuilder {
                         images: RefCell::new(Images::new()),
                         load_options,
                         stylesheets,
+                        diagnostics,
                     };
 
                     document.cascade(&[]);
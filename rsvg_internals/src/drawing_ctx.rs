@@ -21,6 +21,7 @@ use crate::error::{AcquireError, RenderingError};
 use crate::filters;
 use crate::float_eq_cairo::ApproxEqCairo;
 use crate::gradient::{Gradient, GradientUnits, GradientVariant, SpreadMethod};
+use crate::length::Length;
 use crate::marker;
 use crate::node::{CascadedValues, Node, NodeBorrow, NodeDraw};
 use crate::paint_server::{PaintServer, PaintSource};
@@ -28,8 +29,8 @@ use crate::path_builder::*;
 use crate::pattern::{PatternContentUnits, PatternUnits, ResolvedPattern};
 use crate::properties::ComputedValues;
 use crate::property_defs::{
-    ClipRule, FillRule, MixBlendMode, Opacity, Overflow, PaintTarget, ShapeRendering,
-    StrokeDasharray, StrokeLinecap, StrokeLinejoin, TextRendering,
+    ClipRule, EnableBackground, FillRule, MixBlendMode, Opacity, Overflow, PaintTarget,
+    ShapeRendering, StrokeDasharray, StrokeLinecap, StrokeLinejoin, TextRendering,
 };
 use crate::rect::Rect;
 use crate::shapes::Markers;
@@ -127,6 +128,30 @@ impl<'a> PathHelper<'a> {
     }
 }
 
+/// Options that affect how a document is rendered.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderingOptions {
+    /// Whether to render in a reproducible way, for the test suite.
+    pub testing: bool,
+
+    /// Whether filters ignore `color-interpolation-filters` and always operate in sRGB.
+    pub srgb_filters: bool,
+
+    /// The resolution at which filters are rendered, relative to the output; 1.0 is full
+    /// resolution.  Must be in the range `(0.0, 1.0]`.
+    pub filter_scale: f64,
+}
+
+impl Default for RenderingOptions {
+    fn default() -> RenderingOptions {
+        RenderingOptions {
+            testing: false,
+            srgb_filters: false,
+            filter_scale: 1.0,
+        }
+    }
+}
+
 pub struct DrawingCtx {
     initial_transform: Transform,
 
@@ -136,12 +161,16 @@ pub struct DrawingCtx {
     cr_stack: Vec<cairo::Context>,
     cr: cairo::Context,
 
+    // Indices into cr_stack of the layers started by elements with
+    // enable-background="new"; the last one is where BackgroundImage begins.
+    background_stack: Vec<usize>,
+
     view_box_stack: Rc<RefCell<Vec<ViewBox>>>,
 
     drawsub_stack: Vec<Node>,
 
     measuring: bool,
-    testing: bool,
+    options: RenderingOptions,
 }
 
 impl DrawingCtx {
@@ -151,7 +180,7 @@ impl DrawingCtx {
         viewport: Rect,
         dpi: Dpi,
         measuring: bool,
-        testing: bool,
+        options: RenderingOptions,
     ) -> DrawingCtx {
         let initial_transform = Transform::from(cr.get_matrix());
 
@@ -188,10 +217,11 @@ impl DrawingCtx {
             dpi,
             cr_stack: Vec::new(),
             cr: cr.clone(),
+            background_stack: Vec::new(),
             view_box_stack: Rc::new(RefCell::new(view_box_stack)),
             drawsub_stack: Vec::new(),
             measuring,
-            testing,
+            options,
         };
 
         if let Some(node) = node {
@@ -203,6 +233,25 @@ impl DrawingCtx {
         draw_ctx
     }
 
+    /// Creates a `DrawingCtx` for unit tests.
+    ///
+    /// It renders to a new image surface of the given size, at 96 DPI, with the default
+    /// rendering options.
+    #[cfg(test)]
+    pub fn new_for_test(width: i32, height: i32) -> DrawingCtx {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+        let cr = cairo::Context::new(&surface);
+
+        DrawingCtx::new(
+            None,
+            &cr,
+            Rect::from_size(f64::from(width), f64::from(height)),
+            Dpi::new(96.0, 96.0),
+            false,
+            RenderingOptions::default(),
+        )
+    }
+
     pub fn toplevel_viewport(&self) -> Rect {
         self.rect
     }
@@ -211,6 +260,16 @@ impl DrawingCtx {
         self.measuring
     }
 
+    /// Whether filters should skip `color-interpolation-filters` and always work in sRGB.
+    pub fn srgb_filters(&self) -> bool {
+        self.options.srgb_filters
+    }
+
+    /// The resolution at which filters are rendered, relative to the output; 1.0 is full resolution.
+    pub fn filter_scale(&self) -> f64 {
+        self.options.filter_scale
+    }
+
     fn get_transform(&self) -> Transform {
         Transform::from(self.cr.get_matrix())
     }
@@ -549,7 +608,9 @@ impl DrawingCtx {
                 dc.clip_to_node(&clip_in_user_space, acquired_nodes, &dc.empty_bbox())?;
 
                 let is_opaque = approx_eq!(f64, opacity, 1.0);
+                let new_background = values.enable_background() == EnableBackground::New;
                 let needs_temporary_surface = !(is_opaque
+                    && !new_background
                     && filter.is_none()
                     && mask.is_none()
                     && values.mix_blend_mode() == MixBlendMode::Normal
@@ -578,6 +639,12 @@ impl DrawingCtx {
 
                     dc.push_cairo_context(cr);
 
+                    if new_background {
+                        // Once another layer is pushed on top of this one, it
+                        // will live at this index of cr_stack.
+                        dc.background_stack.push(dc.cr_stack.len());
+                    }
+
                     // Draw!
 
                     let mut res = draw_fn(acquired_nodes, dc);
@@ -615,6 +682,10 @@ impl DrawingCtx {
                         dc.cr.get_target()
                     };
 
+                    if new_background {
+                        dc.background_stack.pop();
+                    }
+
                     dc.pop_cairo_context();
 
                     // Set temporary surface as source
@@ -664,7 +735,7 @@ impl DrawingCtx {
                             rsvg_log!(
                                 "element {} references nonexistent mask \"{}\"",
                                 node,
-                                fragment
+                                mask_value.0.source().unwrap_or_default()
                             );
                         }
                     } else {
@@ -1185,10 +1256,7 @@ impl DrawingCtx {
         cr.set_line_join(cairo::LineJoin::from(values.stroke_line_join()));
 
         if let StrokeDasharray(Dasharray::Array(ref dashes)) = values.stroke_dasharray() {
-            let normalized_dashes: Vec<f64> = dashes
-                .iter()
-                .map(|l| l.normalize(values, &params))
-                .collect();
+            let normalized_dashes = Length::normalize_all(dashes, values, &params);
 
             let total_length = normalized_dashes.iter().fold(0.0, |acc, &len| acc + len);
 
@@ -1471,11 +1539,11 @@ impl DrawingCtx {
         width: i32,
         height: i32,
     ) -> Result<SharedImageSurface, cairo::Status> {
-        // TODO: as far as I can tell this should not render elements past the last (topmost) one
-        // with enable-background: new (because technically we shouldn't have been caching them).
-        // Right now there are no enable-background checks whatsoever.
+        // Only the layers since the topmost element with enable-background="new" make up
+        // the background.  If there is no such element, we keep the old behavior of
+        // using everything that has been drawn so far.
         //
-        // Addendum: SVG 2 has deprecated the enable-background property, and replaced it with an
+        // SVG 2 has deprecated the enable-background property, and replaced it with an
         // "isolation" property from the CSS Compositing and Blending spec.
         //
         // Deprecation:
@@ -1489,7 +1557,9 @@ impl DrawingCtx {
         let mut surface = ExclusiveImageSurface::new(width, height, SurfaceType::SRgb)?;
 
         surface.draw(&mut |cr| {
-            for (depth, draw) in self.cr_stack.iter().enumerate() {
+            let start = self.background_stack.last().copied().unwrap_or(0);
+
+            for (depth, draw) in self.cr_stack.iter().enumerate().skip(start) {
                 let affines = CompositingAffines::new(
                     Transform::from(draw.get_matrix()),
                     self.initial_transform_with_offset(),
@@ -1988,7 +2058,7 @@ impl From<&DrawingCtx> for pango::Context {
         // code.
         pangocairo::functions::context_set_resolution(&context, 72.0);
 
-        if draw_ctx.testing {
+        if draw_ctx.options.testing {
             let mut options = cairo::FontOptions::new();
 
             options.set_antialias(cairo::Antialias::Gray);
@@ -220,7 +220,7 @@ impl<T: SetAttributes + Draw> ElementInner<T> {
                 //   self.set_error(e);
                 //   return;
 
-                rsvg_log!("(attribute error: {})", e);
+                rsvg_log!("(attribute error in {}: {})", self, e);
                 Ok(())
             }
         }
@@ -259,6 +259,10 @@ impl<T: SetAttributes + Draw> ElementInner<T> {
     fn is_in_error(&self) -> bool {
         self.result.is_err()
     }
+
+    fn get_error(&self) -> Option<&ElementError> {
+        self.result.as_ref().err()
+    }
 }
 
 impl<T: SetAttributes + Draw> SetAttributes for ElementInner<T> {
@@ -452,6 +456,16 @@ macro_rules! call_inner {
 }
 
 impl Element {
+    /// Returns whether `name` is an SVG element that librsvg knows how to create.
+    ///
+    /// [`new`] creates a [`NonRendering`] element for all other names.
+    ///
+    /// [`new`]: #method.new
+    /// [`NonRendering`]: ../structure/struct.NonRendering.html
+    pub fn is_known_name(name: &QualName) -> bool {
+        name.ns == ns!(svg) && ELEMENT_CREATORS.contains_key(name.local.as_ref())
+    }
+
     /// Takes an XML element name and a list of attribute/value pairs and creates an [`Element`].
     ///
     /// This operation does not fail.  Unknown element names simply produce a [`NonRendering`]
@@ -554,6 +568,10 @@ impl Element {
         call_inner!(self, is_in_error)
     }
 
+    pub fn get_error(&self) -> Option<&ElementError> {
+        call_inner!(self, get_error)
+    }
+
     pub fn as_filter_effect(&self) -> Option<&dyn FilterEffect> {
         match self {
             Element::FeBlend(ref fe) => Some(&fe.element_impl as &dyn FilterEffect),
@@ -922,3 +940,77 @@ mod sizes {
         print_size!(Use);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use markup5ever::{namespace_url, ns, LocalName};
+    use std::ptr;
+
+    use crate::document::load_document;
+
+    fn create(name: &str) -> Element {
+        let bag = unsafe { PropertyBag::new_from_xml2_attributes(0, ptr::null()) };
+
+        Element::new(&QualName::new(None, ns!(svg), LocalName::from(name)), &bag)
+    }
+
+    #[test]
+    fn only_filter_primitives_are_filter_effects() {
+        for name in &[
+            "feBlend",
+            "feColorMatrix",
+            "feComponentTransfer",
+            "feComposite",
+            "feConvolveMatrix",
+            "feDiffuseLighting",
+            "feDisplacementMap",
+            "feFlood",
+            "feGaussianBlur",
+            "feImage",
+            "feMerge",
+            "feMorphology",
+            "feOffset",
+            "feSpecularLighting",
+            "feTile",
+            "feTurbulence",
+        ] {
+            assert!(create(name).as_filter_effect().is_some(), "{}", name);
+        }
+
+        for name in &[
+            "filter",
+            "feDistantLight",
+            "feFuncA",
+            "feMergeNode",
+            "rect",
+            "unknown",
+        ] {
+            assert!(create(name).as_filter_effect().is_none(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn attribute_errors_name_the_element_and_attribute() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <rect id="good" width="10" height="10"/>
+  <rect id="bad" width="10" height="10" transform="foo"/>
+</svg>
+"#,
+        );
+
+        let good = document.lookup_node_by_id("good").unwrap();
+        assert!(!good.borrow_element().is_in_error());
+        assert!(good.borrow_element().get_error().is_none());
+
+        let bad = document.lookup_node_by_id("bad").unwrap();
+        let element = bad.borrow_element();
+        assert!(element.is_in_error());
+        assert_eq!(format!("{}", *element), "rect id=bad");
+
+        let error = element.get_error().unwrap();
+        assert_eq!(error.attr.expanded(), expanded_name!("", "transform"));
+    }
+}
@@ -94,6 +94,21 @@ impl fmt::Display for ElementError {
     }
 }
 
+/// An attribute error found while loading a document, along with the element that has it
+///
+/// Elements with errors are not rendered, so these are collected for diagnostics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingError {
+    pub element_name: QualName,
+    pub error: ElementError,
+}
+
+impl fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}> {}", self.element_name.local, self.error)
+    }
+}
+
 /// Errors returned when looking up a resource by URL reference.
 #[derive(Debug, Clone, PartialEq)]
 pub enum DefsLookupErrorKind {
@@ -197,6 +212,31 @@ impl fmt::Display for AcquireError {
     }
 }
 
+/// Errors from looking up a node's only child of a certain kind.
+///
+/// See [`NodeChildren::exactly_one_child`](../node/trait.NodeChildren.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildCountError {
+    /// No children matched.
+    NoChildren,
+
+    /// More than one child matched.
+    MultipleChildren,
+}
+
+impl error::Error for ChildCountError {}
+
+impl fmt::Display for ChildCountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ChildCountError::NoChildren => write!(f, "expected exactly one child, found none"),
+            ChildCountError::MultipleChildren => {
+                write!(f, "expected exactly one child, found more than one")
+            }
+        }
+    }
+}
+
 /// Helper for converting `Result<O, E>` into `Result<O, ElementError>`
 ///
 /// A `ElementError` requires a `QualName` that corresponds to the attribute to which the
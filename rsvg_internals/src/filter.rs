@@ -1,12 +1,13 @@
 //! The `filter` element.
 
+use cssparser::Parser;
 use markup5ever::{expanded_name, local_name, namespace_url, ns};
 
 use crate::bbox::BoundingBox;
 use crate::coord_units::CoordUnits;
 use crate::drawing_ctx::DrawingCtx;
 use crate::element::{Draw, ElementResult, SetAttributes};
-use crate::error::ValueErrorKind;
+use crate::error::{ParseError, ValueErrorKind};
 use crate::length::*;
 use crate::parsers::{Parse, ParseValue};
 use crate::properties::ComputedValues;
@@ -18,20 +19,37 @@ use crate::transform::Transform;
 pub struct Filter {
     x: Length<Horizontal>,
     y: Length<Vertical>,
-    width: Length<Horizontal>,
-    height: Length<Vertical>,
+    /// `None` if the width is `auto`.
+    width: Option<Length<Horizontal>>,
+    /// `None` if the height is `auto`.
+    height: Option<Length<Vertical>>,
     filterunits: CoordUnits,
     primitiveunits: CoordUnits,
 }
 
+/// The `auto` keyword for the `width` and `height` of the filter effects region.
+///
+/// Along an `auto` dimension, the region expands to contain the output of all the filter
+/// primitives, instead of clipping them to the default -10%..120% of the referencing node.
+///
+/// This is a librsvg extension; SVG 2 has no `auto` value for these attributes.
+struct AutoRegion;
+
+impl Parse for AutoRegion {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<AutoRegion, ParseError<'i>> {
+        parser.expect_ident_matching("auto")?;
+        Ok(AutoRegion)
+    }
+}
+
 impl Default for Filter {
     /// Constructs a new `Filter` with default properties.
     fn default() -> Self {
         Self {
             x: Length::<Horizontal>::parse_str("-10%").unwrap(),
             y: Length::<Vertical>::parse_str("-10%").unwrap(),
-            width: Length::<Horizontal>::parse_str("120%").unwrap(),
-            height: Length::<Vertical>::parse_str("120%").unwrap(),
+            width: Some(Length::<Horizontal>::parse_str("120%").unwrap()),
+            height: Some(Length::<Vertical>::parse_str("120%").unwrap()),
             filterunits: CoordUnits::ObjectBoundingBox,
             primitiveunits: CoordUnits::UserSpaceOnUse,
         }
@@ -48,6 +66,9 @@ impl Filter {
     }
 
     /// Computes and returns the filter effects region.
+    ///
+    /// Along a dimension that is `auto`, the region spans the whole surface, so that it does
+    /// not clip the filter primitives.
     pub fn compute_effects_region(
         &self,
         computed_from_target_node: &ComputedValues,
@@ -78,19 +99,21 @@ impl Filter {
 
         // With filterunits == ObjectBoundingBox, lengths represent fractions or percentages of the
         // referencing node. No units are allowed (it's checked during attribute parsing).
+        //
+        // An auto dimension is replaced below, so its length does not matter here.
         let (x, y, w, h) = if self.filterunits == CoordUnits::ObjectBoundingBox {
             (
                 self.x.length,
                 self.y.length,
-                self.width.length,
-                self.height.length,
+                self.width.map_or(0.0, |w| w.length),
+                self.height.map_or(0.0, |h| h.length),
             )
         } else {
             (
                 self.x.normalize(values, &params),
                 self.y.normalize(values, &params),
-                self.width.normalize(values, &params),
-                self.height.normalize(values, &params),
+                self.width.map_or(0.0, |w| w.normalize(values, &params)),
+                self.height.map_or(0.0, |h| h.normalize(values, &params)),
             )
         };
 
@@ -101,6 +124,19 @@ impl Filter {
         // coordinates in the final surface, because bbox is created with an identity transform.
         bbox.insert(&other_bbox);
 
+        // Auto dimensions span the whole surface.
+        if let Some(ref mut rect) = bbox.rect {
+            if self.width.is_none() {
+                rect.x0 = 0.0;
+                rect.x1 = width;
+            }
+
+            if self.height.is_none() {
+                rect.y0 = 0.0;
+                rect.y1 = height;
+            }
+        }
+
         // Finally, clip to the width and height of our surface.
         let rect = Rect::from_size(width, height);
         let other_bbox = BoundingBox::new().with_rect(rect);
@@ -161,6 +197,12 @@ impl SetAttributes for Filter {
         // Parse the rest of the attributes.
         for (attr, value) in pbag.iter() {
             match attr.expanded() {
+                expanded_name!("", "width") if AutoRegion::parse_str(value).is_ok() => {
+                    self.width = None
+                }
+                expanded_name!("", "height") if AutoRegion::parse_str(value).is_ok() => {
+                    self.height = None
+                }
                 expanded_name!("", "x") => {
                     self.x = attr.parse_and_validate(value, check_units_horizontal)?
                 }
@@ -169,11 +211,13 @@ impl SetAttributes for Filter {
                 }
                 expanded_name!("", "width") => {
                     self.width = attr
-                        .parse_and_validate(value, check_units_horizontal_and_ensure_nonnegative)?
+                        .parse_and_validate(value, check_units_horizontal_and_ensure_nonnegative)
+                        .map(Some)?
                 }
                 expanded_name!("", "height") => {
-                    self.height =
-                        attr.parse_and_validate(value, check_units_vertical_and_ensure_nonnegative)?
+                    self.height = attr
+                        .parse_and_validate(value, check_units_vertical_and_ensure_nonnegative)
+                        .map(Some)?
                 }
                 expanded_name!("", "primitiveUnits") => self.primitiveunits = attr.parse(value)?,
                 _ => (),
@@ -10,7 +10,7 @@ use crate::parsers::{Parse, ParseValue};
 use crate::property_bag::PropertyBag;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Input, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, Input, PrimitiveBase, PrimitiveWithInput};
 
 /// Enumeration of the possible blending modes.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -102,6 +102,10 @@ impl FilterEffect for FeBlend {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
 
 impl Parse for Mode {
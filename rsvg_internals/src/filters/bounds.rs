@@ -70,6 +70,17 @@ impl<'a> BoundsBuilder<'a> {
         self
     }
 
+    /// Adds several filter primitive inputs to the bounding box.
+    ///
+    /// Used by primitives with a variable number of inputs, like feMerge.
+    #[inline]
+    pub fn add_inputs<'i, I>(self, inputs: I) -> Self
+    where
+        I: IntoIterator<Item = &'i FilterInput>,
+    {
+        inputs.into_iter().fold(self, Self::add_input)
+    }
+
     /// Returns the final exact bounds.
     pub fn into_rect(self, draw_ctx: &mut DrawingCtx) -> Rect {
         let mut bbox = self.apply_properties(draw_ctx);
@@ -81,8 +92,11 @@ impl<'a> BoundsBuilder<'a> {
     }
 
     /// Returns the final pixel bounds.
+    ///
+    /// A degenerate subregion, like one from `width="0"`, yields an empty rectangle rather
+    /// than an inverted one.
     pub fn into_irect(self, draw_ctx: &mut DrawingCtx) -> IRect {
-        self.into_rect(draw_ctx).into()
+        non_inverted(self.into_rect(draw_ctx).into())
     }
 
     /// Returns the final pixel bounds without clipping to the filter effects region.
@@ -138,3 +152,59 @@ impl<'a> BoundsBuilder<'a> {
         bbox
     }
 }
+
+/// Clamps `x1` and `y1` so that the rectangle is never inverted.
+fn non_inverted(r: IRect) -> IRect {
+    IRect::new(r.x0, r.y0, r.x1.max(r.x0), r.y1.max(r.y0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::filters::context::FilterOutput;
+    use crate::surface_utils::shared_surface::{SharedImageSurface, SurfaceType};
+    use crate::transform::Transform;
+
+    #[test]
+    fn add_inputs_yields_union_of_input_bounds() {
+        let source = SharedImageSurface::empty(20, 20, SurfaceType::SRgb).unwrap();
+        let ctx = FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+
+        let mut draw_ctx = DrawingCtx::new_for_test(20, 20);
+
+        let inputs: Vec<FilterInput> = [
+            IRect::new(2, 3, 5, 6),
+            IRect::new(4, 1, 8, 4),
+            IRect::new(6, 7, 9, 12),
+        ]
+        .iter()
+        .map(|&bounds| {
+            FilterInput::PrimitiveOutput(FilterOutput {
+                surface: SharedImageSurface::empty(20, 20, SurfaceType::SRgb).unwrap(),
+                bounds,
+            })
+        })
+        .collect();
+
+        let bounds = BoundsBuilder::new(&ctx, None, None, None, None)
+            .add_inputs(&inputs)
+            .into_irect(&mut draw_ctx);
+
+        assert_eq!(bounds, IRect::new(2, 1, 9, 12));
+    }
+
+    #[test]
+    fn zero_width_region_yields_empty_irect() {
+        let r = non_inverted(Rect::new(10.0, 10.0, 10.0, 20.0).into());
+        assert_eq!(r, IRect::new(10, 10, 10, 20));
+        assert!(r.is_empty());
+
+        let r = non_inverted(IRect::new(10, 10, 5, 8));
+        assert_eq!(r, IRect::new(10, 10, 10, 10));
+        assert!(r.is_empty());
+
+        let r = IRect::new(1, 2, 3, 4);
+        assert_eq!(non_inverted(r), r);
+    }
+}
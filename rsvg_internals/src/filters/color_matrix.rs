@@ -16,7 +16,7 @@ use crate::surface_utils::{
 use crate::util::clamp;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// Color matrix operation types.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -50,6 +50,19 @@ impl Default for FeColorMatrix {
     }
 }
 
+impl FeColorMatrix {
+    /// Returns whether the matrix leaves RGB unchanged and computes alpha only from alpha.
+    ///
+    /// Alpha is not gamma-transformed, so such a matrix gives the same result in sRGB and in
+    /// linear RGB, and the color space conversion of the input can be skipped.
+    fn is_alpha_only(&self) -> bool {
+        let identity = Matrix5::<f64>::identity();
+
+        (0..3).all(|row| self.matrix.row(row) == identity.row(row))
+            && (0..3).all(|col| self.matrix[(3, col)] == 0.0)
+    }
+}
+
 #[rustfmt::skip]
 impl SetAttributes for FeColorMatrix {
     fn set_attributes(&mut self, pbag: &PropertyBag<'_>) -> ElementResult {
@@ -213,7 +226,11 @@ impl FilterEffect for FeColorMatrix {
 
     #[inline]
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
-        true
+        !self.is_alpha_only()
+    }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
     }
 }
 
@@ -228,3 +245,115 @@ impl Parse for OperationType {
         )?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::document::{load_document, AcquiredNodes};
+    use crate::node::NodeBorrow;
+    use crate::rect::IRect;
+    use crate::surface_utils::shared_surface::SurfaceType;
+    use crate::transform::Transform;
+
+    #[rustfmt::skip]
+    #[test]
+    fn detects_alpha_only_matrix() {
+        let mut color_matrix = FeColorMatrix::default();
+        assert!(color_matrix.is_alpha_only());
+
+        color_matrix.matrix = Matrix5::new(
+            1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.5, 0.1,
+            0.0, 0.0, 0.0, 0.0, 1.0,
+        );
+        assert!(color_matrix.is_alpha_only());
+        assert!(!color_matrix.is_affected_by_color_interpolation_filters());
+
+        // Alpha computed from the color channels depends on the color space.
+        color_matrix.matrix[(3, 0)] = 0.2125;
+        assert!(!color_matrix.is_alpha_only());
+
+        color_matrix.matrix[(3, 0)] = 0.0;
+        color_matrix.matrix[(0, 0)] = 0.5;
+        assert!(!color_matrix.is_alpha_only());
+        assert!(color_matrix.is_affected_by_color_interpolation_filters());
+    }
+
+    #[test]
+    fn alpha_only_output_is_the_same_in_linear_rgb() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feColorMatrix id="matrix" type="matrix"
+                   values="1 0 0 0 0  0 1 0 0 0  0 0 1 0 0  0 0 0 0.5 0.1"/>
+  </filter>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("matrix").unwrap();
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+
+        // Unpremultiplied, these colors only have channels of 0 or 255, which survive the 8-bit
+        // round trip through linear RGB exactly; any difference would come from the matrix.
+        let pixels = [
+            Pixel {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 255,
+            },
+            Pixel {
+                r: 0,
+                g: 128,
+                b: 0,
+                a: 128,
+            },
+            Pixel {
+                r: 64,
+                g: 64,
+                b: 64,
+                a: 64,
+            },
+        ];
+
+        let mut source = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        source.modify(&mut |data, stride| {
+            for (x, pixel) in pixels.iter().enumerate() {
+                data.set_pixel(stride, *pixel, x as u32, 0);
+            }
+        });
+        let source = source.share().unwrap();
+
+        let mut ctx =
+            FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+        let color_matrix = borrow_element_as!(node, FeColorMatrix);
+
+        assert!(!color_matrix.is_affected_by_color_interpolation_filters());
+
+        let srgb = color_matrix
+            .render(&node, &ctx, &mut acquired_nodes, &mut draw_ctx)
+            .unwrap()
+            .output;
+
+        let linear = ctx
+            .with_linear_rgb(|ctx| {
+                color_matrix.render(&node, ctx, &mut acquired_nodes, &mut draw_ctx)
+            })
+            .unwrap()
+            .output;
+        let linear = linear.surface.to_srgb(linear.bounds).unwrap();
+
+        assert_eq!(srgb.bounds, IRect::from_size(4, 4));
+
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(srgb.surface.get_pixel(x, y), linear.get_pixel(x, y));
+            }
+        }
+    }
+}
@@ -17,7 +17,7 @@ use crate::surface_utils::{
 use crate::util::clamp;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{Channel, FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// The `feComponentTransfer` filter primitive.
 pub struct FeComponentTransfer {
@@ -40,15 +40,6 @@ impl SetAttributes for FeComponentTransfer {
     }
 }
 
-/// Pixel components that can be influenced by `feComponentTransfer`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Channel {
-    R,
-    G,
-    B,
-    A,
-}
-
 /// Component transfer function types.
 enum FunctionType {
     Identity,
@@ -388,4 +379,8 @@ impl FilterEffect for FeComponentTransfer {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
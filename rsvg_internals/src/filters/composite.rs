@@ -1,5 +1,6 @@
 use cssparser::Parser;
-use markup5ever::{expanded_name, local_name, namespace_url, ns};
+use markup5ever::{expanded_name, local_name, namespace_url, ns, QualName};
+use matches::matches;
 
 use crate::document::AcquiredNodes;
 use crate::drawing_ctx::DrawingCtx;
@@ -8,9 +9,11 @@ use crate::error::*;
 use crate::node::Node;
 use crate::parsers::{Parse, ParseValue};
 use crate::property_bag::PropertyBag;
+use crate::rect::IRect;
+use crate::surface_utils::shared_surface::SharedImageSurface;
 
-use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Input, PrimitiveWithInput};
+use super::context::{FilterContext, FilterInput, FilterOutput, FilterResult};
+use super::{FilterEffect, FilterError, Input, PrimitiveBase, PrimitiveWithInput};
 
 /// Enumeration of the possible compositing operations.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -21,6 +24,11 @@ enum Operator {
     Atop,
     Xor,
     Arithmetic,
+
+    /// Adds both inputs, like the `lighter` operator from Filter Effects Level 2.
+    ///
+    /// This is not in SVG 1.1, but it maps directly to Cairo's `ADD` operator.
+    Lighter,
 }
 
 /// The `feComposite` filter primitive.
@@ -58,10 +66,10 @@ impl SetAttributes for FeComposite {
             match attr.expanded() {
                 expanded_name!("", "in2") => self.in2 = Some(attr.parse(value)?),
                 expanded_name!("", "operator") => self.operator = attr.parse(value)?,
-                expanded_name!("", "k1") => self.k1 = attr.parse(value)?,
-                expanded_name!("", "k2") => self.k2 = attr.parse(value)?,
-                expanded_name!("", "k3") => self.k3 = attr.parse(value)?,
-                expanded_name!("", "k4") => self.k4 = attr.parse(value)?,
+                expanded_name!("", "k1") => self.k1 = parse_k(&attr, value)?,
+                expanded_name!("", "k2") => self.k2 = parse_k(&attr, value)?,
+                expanded_name!("", "k3") => self.k3 = parse_k(&attr, value)?,
+                expanded_name!("", "k4") => self.k4 = parse_k(&attr, value)?,
                 _ => (),
             }
         }
@@ -70,6 +78,44 @@ impl SetAttributes for FeComposite {
     }
 }
 
+/// Parses one of the `k1..k4` attributes.
+///
+/// A number that is not finite (for example, one that overflows) is logged and replaced
+/// with zero, so it can't propagate NaN through the whole result.  Other parse errors are
+/// returned as usual.
+fn parse_k(attr: &QualName, value: &str) -> Result<f64, ElementError> {
+    match attr.parse(value) {
+        Err(ElementError {
+            err: ValueErrorKind::Value(ref msg),
+            ..
+        }) => {
+            rsvg_log!(
+                "(feComposite {:?}=\"{}\" is invalid: {}; using 0)",
+                attr.expanded(),
+                value,
+                msg
+            );
+            Ok(0.0)
+        }
+
+        result => result,
+    }
+}
+
+impl FeComposite {
+    /// Returns `true` if the arithmetic operator is selected with all of `k1..k4` set to zero.
+    ///
+    /// This is valid, but it always produces a transparent result, so it is most likely
+    /// an authoring mistake.
+    fn is_arithmetic_without_k_values(&self) -> bool {
+        self.operator == Operator::Arithmetic
+            && self.k1 == 0.0
+            && self.k2 == 0.0
+            && self.k3 == 0.0
+            && self.k4 == 0.0
+    }
+}
+
 impl FilterEffect for FeComposite {
     fn render(
         &self,
@@ -79,6 +125,15 @@ impl FilterEffect for FeComposite {
         draw_ctx: &mut DrawingCtx,
     ) -> Result<FilterResult, FilterError> {
         let input = self.base.get_input(ctx, acquired_nodes, draw_ctx)?;
+
+        // Every operator takes two inputs; per the spec a missing `in2` means the result
+        // of the previous primitive, or SourceGraphic for the first one.
+        if self.in2.is_none() {
+            rsvg_log!(
+                "(feComposite {} has no in2 attribute; using the previous result)",
+                node
+            );
+        }
         let input_2 = ctx.get_input(acquired_nodes, draw_ctx, self.in2.as_ref())?;
         let bounds = self
             .base
@@ -87,7 +142,25 @@ impl FilterEffect for FeComposite {
             .add_input(&input_2)
             .into_irect(draw_ctx);
 
+        // An empty primitive subregion yields a transparent result; don't bother compositing.
+        if bounds.is_empty() {
+            let surface = transparent_surface(&input, &input_2)?;
+
+            return Ok(FilterResult {
+                name: self.base.result.clone(),
+                output: FilterOutput { surface, bounds },
+            });
+        }
+
         let surface = if self.operator == Operator::Arithmetic {
+            if self.is_arithmetic_without_k_values() {
+                rsvg_log!(
+                    "(feComposite {} has operator=\"arithmetic\" but all of k1..k4 are zero; \
+                     the result will be transparent)",
+                    node
+                );
+            }
+
             input.surface().compose_arithmetic(
                 input_2.surface(),
                 bounds,
@@ -96,12 +169,15 @@ impl FilterEffect for FeComposite {
                 self.k3,
                 self.k4,
             )?
-        } else {
+        } else if let Some(compose_bounds) = self.operator.compose_bounds(bounds, &input, &input_2)
+        {
             input.surface().compose(
                 input_2.surface(),
-                bounds,
+                compose_bounds,
                 cairo::Operator::from(self.operator),
             )?
+        } else {
+            transparent_surface(&input, &input_2)?
         };
 
         Ok(FilterResult {
@@ -112,7 +188,13 @@ impl FilterEffect for FeComposite {
 
     #[inline]
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
-        true
+        // `in` and `out` only scale the first input by the alpha of the second one, so
+        // the unpremultiplied colors are the same in either color space.
+        !matches!(self.operator, Operator::In | Operator::Out)
+    }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
     }
 }
 
@@ -126,10 +208,62 @@ impl Parse for Operator {
             "atop" => Operator::Atop,
             "xor" => Operator::Xor,
             "arithmetic" => Operator::Arithmetic,
+            "lighter" => Operator::Lighter,
         )?)
     }
 }
 
+impl Operator {
+    /// Returns the part of `bounds` where compositing `input` with `input_2` can produce
+    /// non-transparent pixels, or `None` if the result is transparent everywhere.
+    ///
+    /// With `in` the result is transparent wherever either input is, so only the
+    /// intersection of both inputs' subregions needs to be composited.  With `out` the
+    /// result is transparent wherever the first input is; outside the second input it is
+    /// the first input unchanged, so only the first input's subregion can be used.
+    fn compose_bounds(
+        self,
+        bounds: IRect,
+        input: &FilterInput,
+        input_2: &FilterInput,
+    ) -> Option<IRect> {
+        match self {
+            Operator::In => restrict_to_input(bounds, input)
+                .and_then(|bounds| restrict_to_input(bounds, input_2)),
+            Operator::Out => restrict_to_input(bounds, input),
+            _ => Some(bounds),
+        }
+    }
+}
+
+/// Intersects `bounds` with the subregion of `input`, if it is known.
+///
+/// Standard inputs cover the whole filter region, so they don't restrict the bounds.
+fn restrict_to_input(bounds: IRect, input: &FilterInput) -> Option<IRect> {
+    match *input {
+        FilterInput::StandardInput(_) => Some(bounds),
+        FilterInput::PrimitiveOutput(ref output) => bounds.intersection(&output.bounds),
+    }
+}
+
+/// Creates a transparent surface for a result that has nothing to composite.
+///
+/// All the results in a filter have the size of the source graphic, so this can't be smaller
+/// than the inputs even though none of its pixels are used.
+fn transparent_surface(
+    input: &FilterInput,
+    input_2: &FilterInput,
+) -> Result<SharedImageSurface, cairo::Status> {
+    SharedImageSurface::empty(
+        input.surface().width(),
+        input.surface().height(),
+        input
+            .surface()
+            .surface_type()
+            .combine(input_2.surface().surface_type()),
+    )
+}
+
 impl From<Operator> for cairo::Operator {
     #[inline]
     fn from(x: Operator) -> Self {
@@ -139,7 +273,280 @@ impl From<Operator> for cairo::Operator {
             Operator::Out => cairo::Operator::Out,
             Operator::Atop => cairo::Operator::Atop,
             Operator::Xor => cairo::Operator::Xor,
+            Operator::Lighter => cairo::Operator::Add,
             _ => panic!("can't convert Operator::Arithmetic to a cairo::Operator"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    use crate::document::load_document;
+    use crate::node::NodeBorrow;
+    use crate::surface_utils::shared_surface::{ExclusiveImageSurface, SurfaceType};
+    use crate::surface_utils::{ImageSurfaceDataExt, Pixel};
+    use crate::transform::Transform;
+
+    #[test]
+    fn renders_with_test_filter_context() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feComposite id="composite" in="SourceGraphic" in2="SourceGraphic"
+                 operator="arithmetic" k2="0.5" k3="0.5"/>
+  </filter>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("composite").unwrap();
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+
+        let opaque = Pixel {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+
+        let mut source = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        source.modify(&mut |data, stride| data.set_pixel(stride, opaque, 1, 2));
+        let source = source.share().unwrap();
+
+        let ctx = FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let result = borrow_element_as!(node, FeComposite)
+            .render(&node, &ctx, &mut acquired_nodes, &mut draw_ctx)
+            .unwrap();
+
+        assert_eq!(result.output.bounds, IRect::from_size(4, 4));
+        assert_eq!(result.output.surface.get_pixel(1, 2), opaque);
+        assert_eq!(result.output.surface.get_pixel(0, 0).a, 0);
+    }
+
+    #[test]
+    fn missing_in2_composites_against_previous_result() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feComposite id="composite" in="SourceGraphic" operator="over"/>
+  </filter>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("composite").unwrap();
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+
+        let red = Pixel {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let blue = Pixel {
+            r: 0,
+            g: 0,
+            b: 255,
+            a: 255,
+        };
+
+        let mut source = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        source.modify(&mut |data, stride| data.set_pixel(stride, red, 1, 2));
+        let source = source.share().unwrap();
+
+        let mut previous = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        previous.modify(&mut |data, stride| data.set_pixel(stride, blue, 3, 0));
+        let previous = previous.share().unwrap();
+
+        let mut ctx =
+            FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+        ctx.store_result(FilterResult {
+            name: None,
+            output: FilterOutput {
+                surface: previous,
+                bounds: IRect::from_size(4, 4),
+            },
+        })
+        .unwrap();
+
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let result = borrow_element_as!(node, FeComposite)
+            .render(&node, &ctx, &mut acquired_nodes, &mut draw_ctx)
+            .unwrap();
+
+        assert_eq!(result.output.surface.get_pixel(1, 2), red);
+        assert_eq!(result.output.surface.get_pixel(3, 0), blue);
+        assert_eq!(result.output.surface.get_pixel(0, 0).a, 0);
+    }
+
+    #[test]
+    fn lighter_adds_inputs() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feComposite id="composite" in="SourceGraphic" in2="SourceGraphic" operator="lighter"/>
+  </filter>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("composite").unwrap();
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+
+        let half_red = Pixel {
+            r: 128,
+            g: 0,
+            b: 0,
+            a: 128,
+        };
+
+        let mut source = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        source.modify(&mut |data, stride| data.set_pixel(stride, half_red, 1, 2));
+        let source = source.share().unwrap();
+
+        let ctx = FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let result = borrow_element_as!(node, FeComposite)
+            .render(&node, &ctx, &mut acquired_nodes, &mut draw_ctx)
+            .unwrap();
+
+        assert_eq!(
+            result.output.surface.get_pixel(1, 2),
+            Pixel {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 255,
+            }
+        );
+        assert_eq!(result.output.surface.get_pixel(0, 0).a, 0);
+    }
+
+    #[test]
+    fn detects_arithmetic_without_k_values() {
+        let mut composite = FeComposite::default();
+        assert!(!composite.is_arithmetic_without_k_values());
+
+        composite.operator = Operator::Arithmetic;
+        assert!(composite.is_arithmetic_without_k_values());
+
+        composite.k4 = 0.5;
+        assert!(!composite.is_arithmetic_without_k_values());
+    }
+
+    #[test]
+    fn replaces_non_finite_k_values_with_zero() {
+        let attr = QualName::new(None, ns!(), local_name!("k1"));
+
+        assert!(approx_eq!(f64, parse_k(&attr, "0.5").unwrap(), 0.5));
+
+        let k = parse_k(&attr, "1e999").unwrap();
+        assert!(k.is_finite());
+        assert!(approx_eq!(f64, k, 0.0));
+
+        assert!(parse_k(&attr, "foo").is_err());
+    }
+
+    #[test]
+    fn restricted_in_matches_full_composition() {
+        let full = IRect::from_size(8, 8);
+
+        let input_with_data = |bounds: IRect, seed: u8| {
+            let mut surface = ExclusiveImageSurface::new(8, 8, SurfaceType::SRgb).unwrap();
+
+            for (y, row) in surface.rows_mut().enumerate() {
+                for (x, p) in row.iter_mut().enumerate() {
+                    if bounds.contains(x as i32, y as i32) {
+                        p.a = 0x80 | seed.wrapping_mul((x + y) as u8);
+                        p.r = seed.min(p.a);
+                        p.g = ((x * 16) as u8).min(p.a);
+                        p.b = ((y * 16) as u8).min(p.a);
+                    }
+                }
+            }
+
+            FilterInput::PrimitiveOutput(FilterOutput {
+                surface: surface.share().unwrap(),
+                bounds,
+            })
+        };
+
+        let input = input_with_data(IRect::new(0, 0, 5, 6), 7);
+        let input_2 = input_with_data(IRect::new(3, 2, 8, 8), 13);
+
+        for &operator in &[Operator::In, Operator::Out] {
+            let compose_bounds = operator.compose_bounds(full, &input, &input_2).unwrap();
+            assert_ne!(compose_bounds, full);
+
+            let restricted = input
+                .surface()
+                .compose(input_2.surface(), compose_bounds, operator.into())
+                .unwrap();
+            let unrestricted = input
+                .surface()
+                .compose(input_2.surface(), full, operator.into())
+                .unwrap();
+
+            for y in 0..8 {
+                for x in 0..8 {
+                    assert_eq!(
+                        restricted.get_pixel(x, y),
+                        unrestricted.get_pixel(x, y),
+                        "{:?} at ({}, {})",
+                        operator,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn disjoint_in_has_no_compose_bounds() {
+        let empty = |bounds| {
+            FilterInput::PrimitiveOutput(FilterOutput {
+                surface: SharedImageSurface::empty(8, 8, SurfaceType::SRgb).unwrap(),
+                bounds,
+            })
+        };
+
+        let input = empty(IRect::new(0, 0, 2, 2));
+        let input_2 = empty(IRect::new(4, 4, 8, 8));
+
+        assert_eq!(
+            Operator::In.compose_bounds(IRect::from_size(8, 8), &input, &input_2),
+            None
+        );
+    }
+
+    #[test]
+    fn alpha_masking_operators_skip_linear_rgb() {
+        let mut composite = FeComposite::default();
+
+        for &(operator, affected) in &[
+            (Operator::Over, true),
+            (Operator::In, false),
+            (Operator::Out, false),
+            (Operator::Atop, true),
+            (Operator::Xor, true),
+            (Operator::Arithmetic, true),
+            (Operator::Lighter, true),
+        ] {
+            composite.operator = operator;
+            assert_eq!(
+                composite.is_affected_by_color_interpolation_filters(),
+                affected
+            );
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use once_cell::sync::Lazy;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::f64;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 
 use crate::bbox::BoundingBox;
 use crate::coord_units::CoordUnits;
@@ -9,7 +12,7 @@ use crate::drawing_ctx::{DrawingCtx, ViewParams};
 use crate::node::{Node, NodeBorrow};
 use crate::parsers::CustomIdent;
 use crate::properties::ComputedValues;
-use crate::rect::IRect;
+use crate::rect::{IRect, Rect};
 use crate::surface_utils::shared_surface::{SharedImageSurface, SurfaceType};
 use crate::transform::Transform;
 
@@ -59,8 +62,12 @@ pub struct FilterContext {
     last_result: Option<FilterOutput>,
     /// Surfaces of the previous filter primitives by name.
     previous_results: HashMap<CustomIdent, FilterOutput>,
+    /// The `SourceAlpha` surface. Computed lazily.
+    source_alpha: LazySurface,
     /// The background surface. Computed lazily.
-    background_surface: RefCell<Option<Result<SharedImageSurface, FilterError>>>,
+    background_surface: LazySurface,
+    /// The `BackgroundAlpha` surface. Computed lazily.
+    background_alpha: LazySurface,
     /// The filter effects region.
     effects_region: BoundingBox,
     /// Whether the currently rendered filter primitive uses linear RGB for color operations.
@@ -68,6 +75,10 @@ pub struct FilterContext {
     /// This affects `get_input()` and `store_result()` which should perform linearization and
     /// unlinearization respectively when this is set to `true`.
     processing_linear_rgb: bool,
+    /// Whether to ignore `color-interpolation-filters` and always operate in sRGB.
+    ///
+    /// This trades accuracy for speed, by skipping the linearization of inputs.
+    srgb_filters: bool,
 
     /// The filter element affine matrix.
     ///
@@ -90,10 +101,84 @@ pub struct FilterContext {
     ///
     /// See the comments for `_affine`, they largely apply here.
     paffine: Transform,
+
+    /// Factor by which the source graphic was scaled down, if filters run at a reduced resolution.
+    ///
+    /// Both affine matrices include this scaling; `into_output()` scales the result back up.
+    resolution_scale: Option<f64>,
+    /// Size of the surface that `into_output()` returns.
+    output_size: (i32, i32),
+    /// Directory where `store_result()` writes each result as a PNG, for debugging.
+    dump_dir: Option<PathBuf>,
+    /// Number of results stored so far; used to number the dumped files.
+    result_index: usize,
+}
+
+/// Returns the directory from the `RSVG_FILTER_DUMP_DIR` environment variable, if it is set.
+fn dump_dir_from_env() -> Option<PathBuf> {
+    static DUMP_DIR: Lazy<Option<PathBuf>> =
+        Lazy::new(|| ::std::env::var_os("RSVG_FILTER_DUMP_DIR").map(PathBuf::from));
+
+    DUMP_DIR.clone()
+}
+
+/// Writes `surface` as a PNG file in `dir`, named after the result's index and name.
+fn dump_result(
+    dir: &Path,
+    index: usize,
+    name: Option<&str>,
+    surface: &SharedImageSurface,
+) -> Result<PathBuf, String> {
+    let name: String = name
+        .unwrap_or("unnamed")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let path = dir.join(format!("{:03}-{}.png", index, name));
+
+    let image = surface
+        .clone()
+        .into_image_surface()
+        .map_err(|e| e.to_string())?;
+    let mut file = File::create(&path).map_err(|e| e.to_string())?;
+    image.write_to_png(&mut file).map_err(|e| e.to_string())?;
+
+    Ok(path)
+}
+
+/// A surface which is computed on first use, and then reused.
+type LazySurface = RefCell<Option<Result<SharedImageSurface, FilterError>>>;
+
+/// Returns the contents of `cache`, computing them with `compute` the first time.
+fn get_or_compute<F>(cache: &LazySurface, compute: F) -> Result<SharedImageSurface, FilterError>
+where
+    F: FnOnce() -> Result<SharedImageSurface, FilterError>,
+{
+    if let Some(ref result) = *cache.borrow() {
+        return result.clone();
+    }
+
+    // The borrow above has ended, so `compute` is free to use other caches.
+    let result = compute();
+    *cache.borrow_mut() = Some(result.clone());
+    result
 }
 
 impl FilterContext {
     /// Creates a new `FilterContext`.
+    ///
+    /// The source graphic is clipped to the filter effects region, so content outside of it
+    /// never reaches the filter primitives.
+    ///
+    /// If the `draw_ctx` asks for filters at a reduced resolution, the source graphic is scaled
+    /// down here, and the filter primitives work in that scaled down space.
     pub fn new(
         filter_node: &Node,
         computed_from_node_being_filtered: &ComputedValues,
@@ -101,13 +186,27 @@ impl FilterContext {
         draw_ctx: &mut DrawingCtx,
         draw_transform: Transform,
         node_bbox: BoundingBox,
-    ) -> Self {
+    ) -> Result<Self, cairo::Status> {
         // The rect can be empty (for example, if the filter is applied to an empty group).
         // However, with userSpaceOnUse it's still possible to create images with a filter.
         let bbox_rect = node_bbox.rect.unwrap_or_default();
 
         let filter = borrow_element_as!(filter_node, Filter);
 
+        let output_size = (source_surface.width(), source_surface.height());
+
+        let resolution_scale = Some(draw_ctx.filter_scale()).filter(|&scale| scale < 1.0);
+
+        let (source_surface, draw_transform) = match resolution_scale {
+            Some(scale) => (
+                source_surface
+                    .scale(IRect::from_size(output_size.0, output_size.1), scale, scale)?
+                    .0,
+                draw_transform.post_scale(scale, scale),
+            ),
+            None => (source_surface, draw_transform),
+        };
+
         let affine = match filter.get_filter_units() {
             CoordUnits::UserSpaceOnUse => draw_transform,
             CoordUnits::ObjectBoundingBox => Transform::new_unchecked(
@@ -136,27 +235,114 @@ impl FilterContext {
 
         let (width, height) = (source_surface.width(), source_surface.height());
 
-        Self {
+        let effects_region = filter.compute_effects_region(
+            computed_from_node_being_filtered,
+            draw_ctx,
+            affine,
+            f64::from(width),
+            f64::from(height),
+        );
+
+        // An effects region without a rect has nothing in it, so none of the source graphic
+        // gets through.  Clipping copies the surface, so it is skipped when the region covers
+        // the whole surface anyway.
+        let region = effects_region.rect.map_or_else(IRect::default, IRect::from);
+        let covers_surface =
+            region.x0 <= 0 && region.y0 <= 0 && region.x1 >= width && region.y1 >= height;
+
+        let source_surface = if covers_surface {
+            source_surface
+        } else {
+            source_surface.clip(region)?
+        };
+
+        Ok(Self {
             node: filter_node.clone(),
             node_bbox,
             computed_from_node_being_filtered: computed_from_node_being_filtered.clone(),
             source_surface,
             last_result: None,
             previous_results: HashMap::new(),
+            source_alpha: RefCell::new(None),
             background_surface: RefCell::new(None),
-            effects_region: filter.compute_effects_region(
-                computed_from_node_being_filtered,
-                draw_ctx,
-                affine,
-                f64::from(width),
-                f64::from(height),
-            ),
+            background_alpha: RefCell::new(None),
+            effects_region,
+            processing_linear_rgb: false,
+            srgb_filters: draw_ctx.srgb_filters(),
+            _affine: affine,
+            paffine,
+            resolution_scale,
+            output_size,
+            dump_dir: dump_dir_from_env(),
+            result_index: 0,
+        })
+    }
+
+    /// Creates a `FilterContext` for unit tests of filter primitives.
+    ///
+    /// Unlike `new()`, this does not need a `<filter>` node or a `DrawingCtx`.  The filter node is
+    /// an empty `<filter>` element, and both the node bounding box and the filter effects region
+    /// cover the whole `source_surface`.
+    #[cfg(test)]
+    pub fn new_for_test(
+        source_surface: SharedImageSurface,
+        affine: Transform,
+        paffine: Transform,
+    ) -> FilterContext {
+        use markup5ever::{local_name, namespace_url, ns, QualName};
+        use std::ptr;
+
+        use crate::node::NodeData;
+        use crate::property_bag::PropertyBag;
+
+        let bag = unsafe { PropertyBag::new_from_xml2_attributes(0, ptr::null()) };
+        let node = Node::new(NodeData::new_element(
+            &QualName::new(None, ns!(svg), local_name!("filter")),
+            &bag,
+        ));
+
+        let (width, height) = (source_surface.width(), source_surface.height());
+        let rect = Rect::from_size(f64::from(width), f64::from(height));
+
+        FilterContext {
+            node,
+            node_bbox: BoundingBox::new().with_rect(rect),
+            computed_from_node_being_filtered: ComputedValues::default(),
+            source_surface,
+            last_result: None,
+            previous_results: HashMap::new(),
+            source_alpha: RefCell::new(None),
+            background_surface: RefCell::new(None),
+            background_alpha: RefCell::new(None),
+            effects_region: BoundingBox::new().with_rect(rect),
             processing_linear_rgb: false,
+            srgb_filters: false,
             _affine: affine,
             paffine,
+            resolution_scale: None,
+            output_size: (width, height),
+            dump_dir: None,
+            result_index: 0,
         }
     }
 
+    /// Returns a description of the filter primitives, one per line.
+    ///
+    /// Each line has the primitive's element, its `in` and `result`, and the subregion it
+    /// would render to.  This is only meant for diagnostics.
+    pub fn describe_chain(&self, draw_ctx: &mut DrawingCtx) -> String {
+        self.node
+            .children()
+            .filter(|c| c.is_element())
+            .filter_map(|c| {
+                let elt = c.borrow_element();
+                elt.as_filter_effect()
+                    .map(|filter| format!("{} {}", c, filter.describe(&c, self, draw_ctx)))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     /// Returns the computed values from the node that referenced this filter.
     #[inline]
     pub fn get_computed_values_from_node_being_filtered(&self) -> &ComputedValues {
@@ -169,69 +355,147 @@ impl FilterContext {
         &self.source_surface
     }
 
+    /// Returns the alpha channel of the source graphic.
+    pub fn source_alpha(&self) -> Result<SharedImageSurface, FilterError> {
+        get_or_compute(&self.source_alpha, || {
+            self.source_graphic()
+                .extract_alpha(self.filter_region())
+                .map_err(FilterError::CairoError)
+        })
+    }
+
     /// Returns the surface corresponding to the background image snapshot.
     pub fn background_image(
         &self,
         draw_ctx: &DrawingCtx,
     ) -> Result<SharedImageSurface, FilterError> {
-        {
-            // At this point either no, or only immutable references to background_surface exist, so
-            // it's ok to make an immutable reference.
-            let bg = self.background_surface.borrow();
-
-            // If background_surface was already computed, return the immutable reference. It will
-            // get bound to the &self lifetime by the function return type.
-            if let Some(ref result) = *bg {
-                return result.clone();
-            }
-        }
+        get_or_compute(&self.background_surface, || {
+            let (width, height) = self.output_size;
+            let snapshot = draw_ctx.get_snapshot(width, height)?;
 
-        // If we got here, then background_surface hasn't been computed yet. This means there are
-        // no references to it and we can create a mutable reference.
-        let mut bg = self.background_surface.borrow_mut();
-
-        *bg = Some(
-            draw_ctx
-                .get_snapshot(self.source_surface.width(), self.source_surface.height())
-                .map_err(FilterError::CairoError),
-        );
+            // The snapshot is at the output resolution; bring it to that of the source graphic.
+            match self.resolution_scale {
+                Some(scale) => Ok(snapshot.scale_to(
+                    self.source_surface.width(),
+                    self.source_surface.height(),
+                    IRect::from_size(self.source_surface.width(), self.source_surface.height()),
+                    scale,
+                    scale,
+                )?),
+                None => Ok(snapshot),
+            }
+        })
+    }
 
-        // Return the only existing reference as immutable.
-        bg.as_ref()
-            .unwrap()
-            .as_ref()
-            .map(|surf| surf.clone())
-            .map_err(|&s| s)
+    /// Returns the alpha channel of the background image snapshot.
+    pub fn background_alpha(
+        &self,
+        draw_ctx: &DrawingCtx,
+    ) -> Result<SharedImageSurface, FilterError> {
+        get_or_compute(&self.background_alpha, || {
+            self.background_image(draw_ctx).and_then(|surface| {
+                surface
+                    .extract_alpha(self.filter_region())
+                    .map_err(FilterError::CairoError)
+            })
+        })
     }
 
     /// Converts this `FilterContext` into the surface corresponding to the output of the filter
     /// chain.
     ///
-    /// The returned surface is in the sRGB color space.
+    /// The returned surface is in the sRGB color space, and it is transparent outside the
+    /// subregion of the last filter primitive.
     // TODO: sRGB conversion should probably be done by the caller.
     #[inline]
     pub fn into_output(self) -> Result<SharedImageSurface, cairo::Status> {
+        let (width, height) = self.output_size;
+
         match self.last_result {
-            Some(FilterOutput { surface, bounds }) => surface.to_srgb(bounds),
-            None => SharedImageSurface::empty(
-                self.source_surface.width(),
-                self.source_surface.height(),
-                SurfaceType::AlphaOnly,
-            ),
+            Some(FilterOutput { surface, bounds }) => {
+                let surface = surface.clip(bounds)?.to_srgb(bounds)?;
+
+                match self.resolution_scale {
+                    Some(scale) => surface.scale_to(
+                        width,
+                        height,
+                        IRect::from_size(width, height),
+                        1.0 / scale,
+                        1.0 / scale,
+                    ),
+                    None => Ok(surface),
+                }
+            }
+            None => SharedImageSurface::empty(width, height, SurfaceType::AlphaOnly),
         }
     }
 
     /// Stores a filter primitive result into the context.
+    ///
+    /// A result named like one of the standard inputs, for example `SourceGraphic`, could never
+    /// be referenced since the `in` attribute gives precedence to the standard input, so it is
+    /// not stored by name.
+    ///
+    /// If a dump directory is set with `set_dump_dir()`, the result is also written there as a
+    /// PNG file.
     #[inline]
     pub fn store_result(&mut self, result: FilterResult) -> Result<(), FilterError> {
+        if let Some(ref dir) = self.dump_dir {
+            let name = result.name.as_ref().map(CustomIdent::as_str);
+
+            match dump_result(dir, self.result_index, name, &result.output.surface) {
+                Ok(path) => rsvg_log!("(dumped filter result to {})", path.display()),
+                Err(e) => rsvg_log!("(could not dump filter result: {})", e),
+            }
+        }
+
+        self.result_index += 1;
+
         if let Some(name) = result.name {
-            self.previous_results.insert(name, result.output.clone());
+            if Input::well_known_names().any(|w| name.as_str().eq_ignore_ascii_case(w)) {
+                rsvg_log!(
+                    "(ignoring result=\"{}\" since it is the name of a standard filter input)",
+                    name.as_str()
+                );
+            } else {
+                self.previous_results.insert(name, result.output.clone());
+            }
         }
 
         self.last_result = Some(result.output);
         Ok(())
     }
 
+    /// Sets the directory where each stored result is written as a PNG file, or `None` to
+    /// stop writing them.
+    ///
+    /// By default, this is taken from the `RSVG_FILTER_DUMP_DIR` environment variable.
+    pub fn set_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.dump_dir = dir;
+    }
+
+    /// Returns the names of the stored filter primitive results, in no particular order.
+    pub fn result_names(&self) -> Vec<&str> {
+        self.previous_results
+            .keys()
+            .map(CustomIdent::as_str)
+            .collect()
+    }
+
+    /// Returns the number of stored named filter primitive results.
+    #[inline]
+    pub fn result_count(&self) -> usize {
+        self.previous_results.len()
+    }
+
+    /// Returns the bounding box of the node being filtered.
+    ///
+    /// This is empty if the node has no geometry, for example, an empty group.
+    #[inline]
+    pub fn bbox(&self) -> Rect {
+        self.node_bbox.rect.unwrap_or_default()
+    }
+
     /// Returns the paffine matrix.
     #[inline]
     pub fn paffine(&self) -> Transform {
@@ -244,6 +508,16 @@ impl FilterContext {
         self.effects_region
     }
 
+    /// Returns the filter effects region in device pixels.
+    ///
+    /// This is the region covered by the filter's own `x`, `y`, `width` and `height`; it is
+    /// the same as the subregion of a primitive with none of those attributes and no inputs.
+    /// If the filter effects region has no rectangle, this is empty.
+    #[inline]
+    pub fn filter_region(&self) -> IRect {
+        self.effects_region.rect.unwrap_or_default().into()
+    }
+
     pub fn get_computed_from_node_being_filtered(&self) -> &ComputedValues {
         &self.computed_from_node_being_filtered
     }
@@ -284,23 +558,14 @@ impl FilterContext {
         match *in_.unwrap() {
             Input::SourceGraphic => Ok(FilterInput::StandardInput(self.source_graphic().clone())),
 
-            Input::SourceAlpha => self
-                .source_graphic()
-                .extract_alpha(self.effects_region().rect.unwrap().into())
-                .map_err(FilterError::CairoError)
-                .map(FilterInput::StandardInput),
+            Input::SourceAlpha => self.source_alpha().map(FilterInput::StandardInput),
 
             Input::BackgroundImage => self
                 .background_image(draw_ctx)
                 .map(FilterInput::StandardInput),
 
             Input::BackgroundAlpha => self
-                .background_image(draw_ctx)
-                .and_then(|surface| {
-                    surface
-                        .extract_alpha(self.effects_region().rect.unwrap().into())
-                        .map_err(FilterError::CairoError)
-                })
+                .background_alpha(draw_ctx)
                 .map(FilterInput::StandardInput),
 
             Input::FillPaint => draw_ctx
@@ -331,12 +596,28 @@ impl FilterContext {
                 .map_err(FilterError::CairoError)
                 .map(FilterInput::StandardInput),
 
-            Input::FilterOutput(ref name) => self
-                .previous_results
-                .get(name)
-                .cloned()
-                .map(FilterInput::PrimitiveOutput)
-                .ok_or(FilterError::InvalidInput),
+            Input::FilterOutput(ref name) => match self.previous_results.get(name) {
+                Some(output) => Ok(FilterInput::PrimitiveOutput(output.clone())),
+
+                // A reference to a result that doesn't exist (yet) is transparent black.
+                None => {
+                    rsvg_log!(
+                        "(no previous filter primitive has result=\"{}\"; using a transparent input)",
+                        name.as_str()
+                    );
+
+                    let surface = SharedImageSurface::empty(
+                        self.source_surface.width(),
+                        self.source_surface.height(),
+                        SurfaceType::SRgb,
+                    )?;
+
+                    Ok(FilterInput::PrimitiveOutput(FilterOutput {
+                        surface,
+                        bounds: self.filter_region(),
+                    }))
+                }
+            },
         }
     }
 
@@ -351,9 +632,7 @@ impl FilterContext {
 
         // Convert the input surface to the desired format.
         let (surface, bounds) = match raw {
-            FilterInput::StandardInput(ref surface) => {
-                (surface, self.effects_region().rect.unwrap().into())
-            }
+            FilterInput::StandardInput(ref surface) => (surface, self.filter_region()),
             FilterInput::PrimitiveOutput(FilterOutput {
                 ref surface,
                 ref bounds,
@@ -376,8 +655,14 @@ impl FilterContext {
     }
 
     /// Calls the given closure with linear RGB processing enabled.
+    ///
+    /// If the context was created for sRGB-only filters, the closure runs in sRGB instead.
     #[inline]
     pub fn with_linear_rgb<T, F: FnOnce(&mut FilterContext) -> T>(&mut self, f: F) -> T {
+        if self.srgb_filters {
+            return f(self);
+        }
+
         self.processing_linear_rgb = true;
         let rv = f(self);
         self.processing_linear_rgb = false;
@@ -401,3 +686,490 @@ impl FilterInput {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matches::matches;
+
+    use crate::document::load_document;
+    use crate::dpi::Dpi;
+    use crate::drawing_ctx::RenderingOptions;
+    use crate::filters::bounds::BoundsBuilder;
+    use crate::float_eq_cairo::ApproxEqCairo;
+    use crate::parsers::Parse;
+    use crate::surface_utils::iterators::Pixels;
+    use crate::surface_utils::shared_surface::ExclusiveImageSurface;
+
+    /// Creates a `FilterContext` for a 4x4 source surface.
+    fn context_for_tests() -> FilterContext {
+        FilterContext::new_for_test(
+            SharedImageSurface::empty(4, 4, SurfaceType::SRgb).unwrap(),
+            Transform::identity(),
+            Transform::identity(),
+        )
+    }
+
+    fn draw_ctx_with_filter_scale(filter_scale: f64) -> DrawingCtx {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 4, 4).unwrap();
+        let cr = cairo::Context::new(&surface);
+
+        DrawingCtx::new(
+            None,
+            &cr,
+            Rect::from_size(4.0, 4.0),
+            Dpi::new(96.0, 96.0),
+            false,
+            RenderingOptions {
+                filter_scale,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn returns_node_bbox() {
+        let rect = Rect::new(1.0, 2.0, 3.0, 5.0);
+
+        let mut ctx = context_for_tests();
+        ctx.node_bbox = BoundingBox::new().with_rect(rect);
+        assert_eq!(ctx.bbox(), rect);
+
+        ctx.node_bbox = BoundingBox::new();
+        assert!(ctx.bbox().is_empty());
+    }
+
+    #[test]
+    fn srgb_filters_skip_linear_rgb() {
+        let mut ctx = context_for_tests();
+        assert!(ctx.with_linear_rgb(|ctx| ctx.processing_linear_rgb));
+        assert!(!ctx.processing_linear_rgb);
+
+        ctx.srgb_filters = true;
+        assert!(!ctx.with_linear_rgb(|ctx| ctx.processing_linear_rgb));
+    }
+
+    #[test]
+    fn source_alpha_is_computed_lazily_and_cached() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg"/>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let ctx = FilterContext::new_for_test(
+            SharedImageSurface::empty(4, 4, SurfaceType::SRgb).unwrap(),
+            Transform::identity(),
+            Transform::identity(),
+        );
+
+        // A chain which does not reference the alpha inputs does not compute them.
+        ctx.get_input(&mut acquired_nodes, &mut draw_ctx, None)
+            .unwrap();
+        ctx.get_input(
+            &mut acquired_nodes,
+            &mut draw_ctx,
+            Some(&Input::SourceGraphic),
+        )
+        .unwrap();
+        assert!(ctx.source_alpha.borrow().is_none());
+        assert!(ctx.background_alpha.borrow().is_none());
+
+        let raw_surface = |input: FilterInput| {
+            input
+                .surface()
+                .to_cairo_pattern()
+                .get_surface()
+                .to_raw_none()
+        };
+
+        let first = ctx
+            .get_input(
+                &mut acquired_nodes,
+                &mut draw_ctx,
+                Some(&Input::SourceAlpha),
+            )
+            .unwrap();
+        assert!(ctx.source_alpha.borrow().is_some());
+
+        let second = ctx
+            .get_input(
+                &mut acquired_nodes,
+                &mut draw_ctx,
+                Some(&Input::SourceAlpha),
+            )
+            .unwrap();
+        assert_eq!(raw_surface(first), raw_surface(second));
+    }
+
+    #[test]
+    fn filter_region_matches_default_primitive_subregion() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter id="filter" filterUnits="userSpaceOnUse" x="2" y="3" width="8" height="6"/>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("filter").unwrap();
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let source = SharedImageSurface::empty(16, 16, SurfaceType::SRgb).unwrap();
+
+        let ctx = FilterContext::new(
+            &node,
+            &ComputedValues::default(),
+            source,
+            &mut draw_ctx,
+            Transform::identity(),
+            BoundingBox::new().with_rect(Rect::from_size(16.0, 16.0)),
+        )
+        .unwrap();
+
+        assert_eq!(ctx.filter_region(), IRect::new(2, 3, 10, 9));
+        assert_eq!(
+            ctx.filter_region(),
+            BoundsBuilder::new(&ctx, None, None, None, None).into_irect(&mut draw_ctx)
+        );
+    }
+
+    #[test]
+    fn reduced_resolution_scales_source_and_output() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter id="filter" filterUnits="userSpaceOnUse" x="0" y="0" width="16" height="16"/>
+</svg>
+"#,
+        );
+        let node = document.lookup_node_by_id("filter").unwrap();
+        let mut draw_ctx = draw_ctx_with_filter_scale(0.5);
+
+        let mut source = ExclusiveImageSurface::new(16, 16, SurfaceType::SRgb).unwrap();
+        source
+            .draw(&mut |cr| {
+                cr.set_source_rgba(0.0, 1.0, 0.0, 1.0);
+                cr.paint();
+                Ok(())
+            })
+            .unwrap();
+
+        let mut ctx = FilterContext::new(
+            &node,
+            &ComputedValues::default(),
+            source.share().unwrap(),
+            &mut draw_ctx,
+            Transform::identity(),
+            BoundingBox::new().with_rect(Rect::from_size(16.0, 16.0)),
+        )
+        .unwrap();
+
+        // The filter primitives work on surfaces of a quarter of the size.
+        assert_eq!(ctx.source_graphic().width(), 8);
+        assert_eq!(ctx.source_graphic().height(), 8);
+        assert_eq!(ctx.effects_region().rect, Some(Rect::from_size(8.0, 8.0)));
+        assert_approx_eq_cairo!(ctx.paffine().xx, 0.5);
+
+        let surface = ctx.source_graphic().clone();
+        ctx.store_result(FilterResult {
+            name: None,
+            output: FilterOutput {
+                surface,
+                bounds: IRect::from_size(8, 8),
+            },
+        })
+        .unwrap();
+
+        let output = ctx.into_output().unwrap();
+        assert_eq!(output.width(), 16);
+        assert_eq!(output.height(), 16);
+        assert_eq!(output.get_pixel(8, 8).a, 255);
+    }
+
+    #[test]
+    fn missing_result_is_transparent_input() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg"/>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let mut source = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        source
+            .draw(&mut |cr| {
+                cr.set_source_rgba(0.0, 1.0, 0.0, 1.0);
+                cr.paint();
+                Ok(())
+            })
+            .unwrap();
+
+        let ctx = FilterContext::new_for_test(
+            source.share().unwrap(),
+            Transform::identity(),
+            Transform::identity(),
+        );
+
+        let name = CustomIdent::parse_str("doesNotExist").unwrap();
+        let input = ctx
+            .get_input(
+                &mut acquired_nodes,
+                &mut draw_ctx,
+                Some(&Input::FilterOutput(name)),
+            )
+            .unwrap();
+
+        match input {
+            FilterInput::PrimitiveOutput(ref output) => {
+                assert_eq!(output.bounds, IRect::from_size(4, 4))
+            }
+            _ => panic!("expected a primitive output"),
+        }
+
+        assert_eq!(input.surface().width(), 4);
+        assert_eq!(input.surface().height(), 4);
+        assert!(Pixels::new(input.surface()).all(|(_, _, p)| p.a == 0));
+    }
+
+    #[test]
+    fn standard_input_names_are_not_stored_as_results() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg"/>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let mut ctx = context_for_tests();
+        let source = ctx.source_graphic().clone();
+
+        let other = SharedImageSurface::empty(2, 2, SurfaceType::SRgb).unwrap();
+        ctx.store_result(FilterResult {
+            name: Some(CustomIdent::parse_str("SourceGraphic").unwrap()),
+            output: FilterOutput {
+                surface: other,
+                bounds: IRect::from_size(2, 2),
+            },
+        })
+        .unwrap();
+
+        assert!(ctx.result_names().is_empty());
+
+        // The result still feeds the next primitive if it has no "in"...
+        let input = ctx
+            .get_input(&mut acquired_nodes, &mut draw_ctx, None)
+            .unwrap();
+        assert_eq!(input.surface().width(), 2);
+
+        // ... but in="SourceGraphic" means the source graphic.
+        let input = ctx
+            .get_input(
+                &mut acquired_nodes,
+                &mut draw_ctx,
+                Some(&Input::parse_str("SourceGraphic").unwrap()),
+            )
+            .unwrap();
+        assert!(matches!(input, FilterInput::StandardInput(_)));
+        assert_eq!(input.surface().width(), source.width());
+    }
+
+    #[test]
+    fn default_input_without_last_result_is_source_graphic() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg"/>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let mut ctx = context_for_tests();
+        let surface = ctx.source_graphic().clone();
+
+        ctx.store_result(FilterResult {
+            name: None,
+            output: FilterOutput {
+                surface,
+                bounds: IRect::new(1, 1, 2, 2),
+            },
+        })
+        .unwrap();
+        ctx.last_result = None;
+
+        // A standard input makes the primitive use the whole filter effects region,
+        // instead of the bounds of a previous result.
+        let input = ctx
+            .get_input_raw(&mut acquired_nodes, &mut draw_ctx, None)
+            .unwrap();
+        assert!(matches!(input, FilterInput::StandardInput(_)));
+        assert_eq!(input.surface().width(), 4);
+        assert_eq!(input.surface().height(), 4);
+    }
+
+    #[test]
+    fn result_cycle_is_resolved_in_document_order() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg"/>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let mut ctx = context_for_tests();
+
+        let a = Input::parse_str("a").unwrap();
+        let b = Input::parse_str("b").unwrap();
+
+        // <feOffset in="b" result="a"/> refers to a result that comes later, which is
+        // transparent black rather than a recursive evaluation of the second primitive.
+        let input = ctx
+            .get_input(&mut acquired_nodes, &mut draw_ctx, Some(&b))
+            .unwrap();
+        assert!(Pixels::new(input.surface()).all(|(_, _, p)| p.a == 0));
+
+        let mut surface = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        surface
+            .draw(&mut |cr| {
+                cr.set_source_rgba(0.0, 1.0, 0.0, 1.0);
+                cr.paint();
+                Ok(())
+            })
+            .unwrap();
+
+        ctx.store_result(FilterResult {
+            name: Some(CustomIdent::parse_str("a").unwrap()),
+            output: FilterOutput {
+                surface: surface.share().unwrap(),
+                bounds: IRect::from_size(4, 4),
+            },
+        })
+        .unwrap();
+
+        // <feOffset in="a" result="b"/> sees the first primitive's output.
+        let input = ctx
+            .get_input(&mut acquired_nodes, &mut draw_ctx, Some(&a))
+            .unwrap();
+        assert!(Pixels::new(input.surface()).all(|(_, _, p)| p.a == 255));
+    }
+
+    #[test]
+    fn dumps_results_to_directory() {
+        let dir = std::env::temp_dir().join(format!("rsvg-filter-dump-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut ctx = context_for_tests();
+        ctx.set_dump_dir(Some(dir.clone()));
+
+        let surface = ctx.source_graphic().clone();
+
+        for name in &[Some("blur"), None] {
+            ctx.store_result(FilterResult {
+                name: name.map(|n| CustomIdent::parse_str(n).unwrap()),
+                output: FilterOutput {
+                    surface: surface.clone(),
+                    bounds: IRect::from_size(4, 4),
+                },
+            })
+            .unwrap();
+        }
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort_unstable();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, vec!["000-blur.png", "001-unnamed.png"]);
+    }
+
+    #[test]
+    fn lists_named_results() {
+        let mut ctx = context_for_tests();
+        let surface = ctx.source_graphic().clone();
+
+        for name in &[Some("blur"), None, Some("offset")] {
+            ctx.store_result(FilterResult {
+                name: name.map(|n| CustomIdent::parse_str(n).unwrap()),
+                output: FilterOutput {
+                    surface: surface.clone(),
+                    bounds: IRect::from_size(4, 4),
+                },
+            })
+            .unwrap();
+        }
+
+        assert_eq!(ctx.result_count(), 2);
+
+        let mut names = ctx.result_names();
+        names.sort_unstable();
+        assert_eq!(names, vec!["blur", "offset"]);
+    }
+
+    #[test]
+    fn output_is_transparent_outside_last_bounds() {
+        let mut ctx = context_for_tests();
+
+        let mut surface = ExclusiveImageSurface::new(4, 4, SurfaceType::SRgb).unwrap();
+        surface
+            .draw(&mut |cr| {
+                cr.set_source_rgba(0.0, 1.0, 0.0, 1.0);
+                cr.paint();
+                Ok(())
+            })
+            .unwrap();
+
+        ctx.store_result(FilterResult {
+            name: None,
+            output: FilterOutput {
+                surface: surface.share().unwrap(),
+                bounds: IRect::new(1, 1, 3, 3),
+            },
+        })
+        .unwrap();
+
+        let output = ctx.into_output().unwrap();
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let inside = (1..3).contains(&x) && (1..3).contains(&y);
+                let expected = if inside { 255 } else { 0 };
+                assert_eq!(output.get_pixel(x, y).a, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn describes_filter_chain() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter id="filter" filterUnits="userSpaceOnUse" primitiveUnits="userSpaceOnUse">
+    <feFlood result="flood" x="1" y="1" width="2" height="2"/>
+    <feOffset in="flood" result="moved" dx="1"/>
+  </filter>
+</svg>
+"#,
+        );
+        let mut ctx = context_for_tests();
+        ctx.node = document.lookup_node_by_id("filter").unwrap();
+
+        let mut draw_ctx = DrawingCtx::new_for_test(4, 4);
+
+        let description = ctx.describe_chain(&mut draw_ctx);
+        let lines: Vec<&str> = description.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("feFlood"));
+        assert!(lines[0].contains("result=flood"));
+        assert!(lines[1].contains("feOffset"));
+        assert!(lines[1].contains("in=flood"));
+        assert!(lines[1].contains("result=moved"));
+    }
+}
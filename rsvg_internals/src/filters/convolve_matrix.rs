@@ -19,7 +19,9 @@ use crate::surface_utils::{
 use crate::util::clamp;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{
+    check_kernel_unit_length, FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput,
+};
 
 /// The `feConvolveMatrix` filter primitive.
 pub struct FeConvolveMatrix {
@@ -84,15 +86,7 @@ impl SetAttributes for FeConvolveMatrix {
                 expanded_name!("", "edgeMode") => self.edge_mode = attr.parse(value)?,
                 expanded_name!("", "kernelUnitLength") => {
                     let NumberOptionalNumber(x, y) =
-                        attr.parse_and_validate(value, |v: NumberOptionalNumber<f64>| {
-                            if v.0 > 0.0 && v.1 > 0.0 {
-                                Ok(v)
-                            } else {
-                                Err(ValueErrorKind::value_error(
-                                    "kernelUnitLength can't be less or equal to zero",
-                                ))
-                            }
-                        })?;
+                        attr.parse_and_validate(value, check_kernel_unit_length)?;
 
                     self.kernel_unit_length = Some((x, y))
                 }
@@ -327,16 +321,9 @@ impl FilterEffect for FeConvolveMatrix {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
-}
 
-impl Parse for EdgeMode {
-    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
-        Ok(parse_identifiers!(
-            parser,
-            "duplicate" => EdgeMode::Duplicate,
-            "wrap" => EdgeMode::Wrap,
-            "none" => EdgeMode::None,
-        )?)
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
     }
 }
 
@@ -1,34 +1,23 @@
-use cssparser::Parser;
 use markup5ever::{expanded_name, local_name, namespace_url, ns};
 
 use crate::document::AcquiredNodes;
 use crate::drawing_ctx::DrawingCtx;
 use crate::element::{ElementResult, SetAttributes};
-use crate::error::*;
 use crate::node::Node;
-use crate::parsers::{Parse, ParseValue};
+use crate::parsers::ParseValue;
 use crate::property_bag::PropertyBag;
 use crate::surface_utils::{iterators::Pixels, shared_surface::ExclusiveImageSurface};
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Input, PrimitiveWithInput};
-
-/// Enumeration of the color channels the displacement map can source.
-#[derive(Clone, Copy)]
-enum ColorChannel {
-    R,
-    G,
-    B,
-    A,
-}
+use super::{Channel, FilterEffect, FilterError, Input, PrimitiveBase, PrimitiveWithInput};
 
 /// The `feDisplacementMap` filter primitive.
 pub struct FeDisplacementMap {
     base: PrimitiveWithInput,
     in2: Option<Input>,
     scale: f64,
-    x_channel_selector: ColorChannel,
-    y_channel_selector: ColorChannel,
+    x_channel_selector: Channel,
+    y_channel_selector: Channel,
 }
 
 impl Default for FeDisplacementMap {
@@ -39,8 +28,8 @@ impl Default for FeDisplacementMap {
             base: PrimitiveWithInput::new::<Self>(),
             in2: None,
             scale: 0.0,
-            x_channel_selector: ColorChannel::A,
-            y_channel_selector: ColorChannel::A,
+            x_channel_selector: Channel::A,
+            y_channel_selector: Channel::A,
         }
     }
 }
@@ -99,17 +88,10 @@ impl FilterEffect for FeDisplacementMap {
 
         surface.draw(&mut |cr| {
             for (x, y, displacement_pixel) in Pixels::within(&displacement_surface, bounds) {
-                let get_value = |channel| match channel {
-                    ColorChannel::R => displacement_pixel.r,
-                    ColorChannel::G => displacement_pixel.g,
-                    ColorChannel::B => displacement_pixel.b,
-                    ColorChannel::A => displacement_pixel.a,
-                };
-
                 let process = |x| f64::from(x) / 255.0 - 0.5;
 
-                let dx = process(get_value(self.x_channel_selector));
-                let dy = process(get_value(self.y_channel_selector));
+                let dx = process(self.x_channel_selector.get(displacement_pixel));
+                let dy = process(self.y_channel_selector.get(displacement_pixel));
 
                 let x = f64::from(x);
                 let y = f64::from(y);
@@ -144,16 +126,8 @@ impl FilterEffect for FeDisplacementMap {
         // only needed for in2.
         true
     }
-}
 
-impl Parse for ColorChannel {
-    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
-        Ok(parse_identifiers!(
-            parser,
-            "R" => ColorChannel::R,
-            "G" => ColorChannel::G,
-            "B" => ColorChannel::B,
-            "A" => ColorChannel::A,
-        )?)
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
     }
 }
@@ -1,15 +1,18 @@
 use std::error::Error;
 use std::fmt;
 
-use crate::error::RenderingError;
+use crate::error::{ChildCountError, RenderingError};
 
 /// An enumeration of errors that can occur during filter primitive rendering.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+///
+/// These are logged along with the filter primitive that failed, so they don't
+/// need to identify the primitive themselves.
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum FilterError {
     /// The units on the filter bounds are invalid
     InvalidUnits,
-    /// The filter was passed invalid input (the `in` attribute).
-    InvalidInput,
+    /// An `feImage` has no `href`, or its `href` could not be loaded.
+    InvalidImageHref(Option<String>),
     /// The filter input surface has an unsuccessful status.
     BadInputSurfaceStatus(cairo::Status),
     /// A Cairo error.
@@ -18,9 +21,9 @@ pub enum FilterError {
     /// status.
     CairoError(cairo::Status),
     /// A lighting filter has none or multiple light sources.
-    InvalidLightSourceCount,
-    /// A lighting filter input surface is too small.
-    LightingInputTooSmall,
+    InvalidLightSourceCount(ChildCountError),
+    /// A lighting filter's `kernelUnitLength` is zero or infinite in device space.
+    InvalidKernelUnitLength,
     /// Child node was in error.
     ChildNodeInError,
 }
@@ -34,15 +37,20 @@ impl fmt::Display for FilterError {
                 f,
                 "unit identifiers are not allowed with primitiveUnits set to objectBoundingBox"
             ),
-            FilterError::InvalidInput => write!(f, "invalid value of the `in` attribute"),
+            FilterError::InvalidImageHref(None) => write!(f, "feImage has no href"),
+            FilterError::InvalidImageHref(Some(ref href)) => {
+                write!(f, "could not load feImage href \"{}\"", href)
+            }
             FilterError::BadInputSurfaceStatus(ref status) => {
                 write!(f, "invalid status of the input surface: {}", status)
             }
             FilterError::CairoError(ref status) => write!(f, "Cairo error: {}", status),
-            FilterError::InvalidLightSourceCount => write!(f, "invalid light source count"),
-            FilterError::LightingInputTooSmall => write!(
+            FilterError::InvalidLightSourceCount(ref e) => {
+                write!(f, "invalid light source count: {}", e)
+            }
+            FilterError::InvalidKernelUnitLength => write!(
                 f,
-                "lighting filter input surface is too small (less than 2×2 pixels)"
+                "kernelUnitLength is zero or infinite after applying the transform"
             ),
             FilterError::ChildNodeInError => write!(f, "child node was in error"),
         }
@@ -68,3 +76,28 @@ impl From<RenderingError> for FilterError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_messages_include_details() {
+        assert_eq!(
+            FilterError::InvalidImageHref(None).to_string(),
+            "feImage has no href"
+        );
+        assert_eq!(
+            FilterError::InvalidImageHref(Some(String::from("foo.png"))).to_string(),
+            "could not load feImage href \"foo.png\""
+        );
+        assert_eq!(
+            FilterError::InvalidLightSourceCount(ChildCountError::NoChildren).to_string(),
+            "invalid light source count: expected exactly one child, found none"
+        );
+        assert_eq!(
+            FilterError::CairoError(cairo::Status::NoMemory).to_string(),
+            format!("Cairo error: {}", cairo::Status::NoMemory)
+        );
+    }
+}
@@ -5,7 +5,7 @@ use crate::node::{CascadedValues, Node};
 use crate::property_bag::PropertyBag;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Primitive};
+use super::{resolve_color, FilterEffect, FilterError, Primitive, PrimitiveBase};
 
 /// The `feFlood` filter primitive.
 pub struct FeFlood {
@@ -44,10 +44,7 @@ impl FilterEffect for FeFlood {
         let cascaded = CascadedValues::new_from_node(node);
         let values = cascaded.get();
 
-        let color = match values.flood_color().0 {
-            cssparser::Color::CurrentColor => values.color().0,
-            cssparser::Color::RGBA(rgba) => rgba,
-        };
+        let color = resolve_color(values.flood_color().0, values.color().0);
         let opacity = values.flood_opacity().0;
 
         let surface = ctx.source_graphic().flood(bounds, color, opacity)?;
@@ -62,4 +59,8 @@ impl FilterEffect for FeFlood {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         false
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
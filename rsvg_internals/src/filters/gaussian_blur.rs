@@ -18,7 +18,7 @@ use crate::surface_utils::{
 };
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// The maximum gaussian blur kernel size.
 ///
@@ -29,6 +29,7 @@ const MAXIMUM_KERNEL_SIZE: usize = 500;
 pub struct FeGaussianBlur {
     base: PrimitiveWithInput,
     std_deviation: (f64, f64),
+    edge_mode: EdgeMode,
 }
 
 impl Default for FeGaussianBlur {
@@ -38,6 +39,7 @@ impl Default for FeGaussianBlur {
         FeGaussianBlur {
             base: PrimitiveWithInput::new::<Self>(),
             std_deviation: (0.0, 0.0),
+            edge_mode: EdgeMode::None,
         }
     }
 }
@@ -62,6 +64,12 @@ impl SetAttributes for FeGaussianBlur {
             self.std_deviation = (tuple.0, tuple.1);
         }
 
+        for (attr, value) in pbag.iter() {
+            if let expanded_name!("", "edgeMode") = attr.expanded() {
+                self.edge_mode = attr.parse(value)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -174,6 +182,7 @@ fn gaussian_blur(
     bounds: IRect,
     std_deviation: f64,
     vertical: bool,
+    edge_mode: EdgeMode,
 ) -> Result<SharedImageSurface, FilterError> {
     let kernel = gaussian_kernel(std_deviation);
     let (rows, cols) = if vertical {
@@ -191,7 +200,7 @@ fn gaussian_blur(
         bounds,
         ((cols / 2) as i32, (rows / 2) as i32),
         &kernel,
-        EdgeMode::None,
+        edge_mode,
     )?)
 }
 
@@ -221,22 +230,26 @@ impl FilterEffect for FeGaussianBlur {
         // (so the image is alpha-only). We can use this to not waste time processing the other
         // channels.
 
+        // The box blurs treat pixels outside the bounds as transparent, so they can only be
+        // used for edgeMode="none".
+        let use_box_blur = self.edge_mode == EdgeMode::None;
+
         // Horizontal convolution.
-        let horiz_result_surface = if std_x >= 2.0 {
+        let horiz_result_surface = if use_box_blur && std_x >= 2.0 {
             // The spec says for deviation >= 2.0 three box blurs can be used as an optimization.
             three_box_blurs::<Horizontal>(input.surface(), bounds, std_x)?
         } else if std_x != 0.0 {
-            gaussian_blur(input.surface(), bounds, std_x, false)?
+            gaussian_blur(input.surface(), bounds, std_x, false, self.edge_mode)?
         } else {
             input.surface().clone()
         };
 
         // Vertical convolution.
-        let output_surface = if std_y >= 2.0 {
+        let output_surface = if use_box_blur && std_y >= 2.0 {
             // The spec says for deviation >= 2.0 three box blurs can be used as an optimization.
             three_box_blurs::<Vertical>(&horiz_result_surface, bounds, std_y)?
         } else if std_y != 0.0 {
-            gaussian_blur(&horiz_result_surface, bounds, std_y, true)?
+            gaussian_blur(&horiz_result_surface, bounds, std_y, true, self.edge_mode)?
         } else {
             horiz_result_surface
         };
@@ -254,4 +267,83 @@ impl FilterEffect for FeGaussianBlur {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::document::load_document;
+    use crate::node::NodeBorrow;
+    use crate::surface_utils::shared_surface::{ExclusiveImageSurface, SurfaceType};
+    use crate::surface_utils::{ImageSurfaceDataExt, Pixel};
+    use crate::transform::Transform;
+
+    #[test]
+    fn content_at_region_edge_does_not_wrap_around() {
+        const SIZE: i32 = 32;
+
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feGaussianBlur id="none" stdDeviation="1"/>
+    <feGaussianBlur id="none_box" stdDeviation="3"/>
+    <feGaussianBlur id="duplicate" stdDeviation="1" edgeMode="duplicate"/>
+  </filter>
+</svg>
+"#,
+        );
+        let mut acquired_nodes = AcquiredNodes::new(&document);
+
+        // An opaque column along the left edge of the filter region.
+        let opaque = Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+
+        let mut source = ExclusiveImageSurface::new(SIZE, SIZE, SurfaceType::SRgb).unwrap();
+        source.modify(&mut |data, stride| {
+            for y in 0..SIZE as u32 {
+                data.set_pixel(stride, opaque, 0, y);
+            }
+        });
+        let source = source.share().unwrap();
+
+        let ctx = FilterContext::new_for_test(source, Transform::identity(), Transform::identity());
+
+        let mut draw_ctx = DrawingCtx::new_for_test(SIZE, SIZE);
+
+        let mut blur = |id| {
+            let node = document.lookup_node_by_id(id).unwrap();
+            let result = borrow_element_as!(node, FeGaussianBlur)
+                .render(&node, &ctx, &mut acquired_nodes, &mut draw_ctx)
+                .unwrap();
+            result.output.surface
+        };
+
+        let none = blur("none");
+        let none_box = blur("none_box");
+        let duplicate = blur("duplicate");
+
+        let last = SIZE as u32 - 1;
+        for surface in &[&none, &none_box, &duplicate] {
+            for y in 0..SIZE as u32 {
+                assert_eq!(surface.get_pixel(last, y).a, 0);
+            }
+        }
+
+        // With edgeMode="none" the samples to the left of the region are transparent, so
+        // the edge gets lighter; duplicating the edge keeps more of its opacity.
+        let middle = SIZE as u32 / 2;
+        assert!(none.get_pixel(0, middle).a < 255);
+        assert!(none_box.get_pixel(0, middle).a < 255);
+        assert!(duplicate.get_pixel(0, middle).a > none.get_pixel(0, middle).a);
+    }
 }
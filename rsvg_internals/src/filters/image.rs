@@ -14,7 +14,7 @@ use crate::rect::Rect;
 use crate::viewbox::ViewBox;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Primitive};
+use super::{FilterEffect, FilterError, Primitive, PrimitiveBase};
 
 /// The `feImage` filter primitive.
 pub struct FeImage {
@@ -47,7 +47,7 @@ impl FeImage {
     ) -> Result<FilterResult, FilterError> {
         let acquired_drawable = acquired_nodes
             .acquire(fragment)
-            .map_err(|_| FilterError::InvalidInput)?;
+            .map_err(|_| FilterError::InvalidImageHref(Some(fragment.to_string())))?;
         let drawable = acquired_drawable.get();
 
         let node_being_filtered_values = ctx.get_computed_values_from_node_being_filtered();
@@ -86,7 +86,7 @@ impl FeImage {
         // FIXME: translate the error better here
         let image = acquired_nodes
             .lookup_image(url)
-            .map_err(|_| FilterError::InvalidInput)?;
+            .map_err(|_| FilterError::InvalidImageHref(Some(url.to_string())))?;
 
         let rect = self.aspect.compute(
             &ViewBox(Rect::from_size(
@@ -161,7 +161,7 @@ impl FilterEffect for FeImage {
             Some(Href::WithFragment(ref frag)) => {
                 self.render_node(ctx, acquired_nodes, draw_ctx, bounds, frag)
             }
-            _ => Err(FilterError::InvalidInput),
+            None => Err(FilterError::InvalidImageHref(None)),
         }
     }
 
@@ -169,4 +169,8 @@ impl FilterEffect for FeImage {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         false
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
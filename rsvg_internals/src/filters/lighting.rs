@@ -13,10 +13,11 @@ use crate::drawing_ctx::DrawingCtx;
 use crate::element::{Draw, Element, ElementResult, SetAttributes};
 use crate::error::*;
 use crate::filters::{
+    check_kernel_unit_length,
     context::{FilterContext, FilterOutput, FilterResult},
-    FilterEffect, FilterError, PrimitiveWithInput,
+    resolve_color, FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput,
 };
-use crate::node::{CascadedValues, Node, NodeBorrow};
+use crate::node::{CascadedValues, Node, NodeBorrow, NodeChildren};
 use crate::parsers::{NumberOptionalNumber, ParseValue};
 use crate::property_bag::PropertyBag;
 use crate::rect::IRect;
@@ -24,6 +25,7 @@ use crate::surface_utils::{
     shared_surface::{ExclusiveImageSurface, SharedImageSurface, SurfaceType},
     ImageSurfaceDataExt, Pixel,
 };
+use crate::transform::Transform;
 use crate::util::clamp;
 
 /// A light source with affine transformations applied.
@@ -254,6 +256,12 @@ impl Common {
             kernel_unit_length: None,
         }
     }
+
+    /// Returns whether `surfaceScale` is zero, in which case all normals are (0, 0, 1).
+    #[inline]
+    fn surface_is_flat(&self) -> bool {
+        approx_eq!(f64, self.surface_scale, 0.0)
+    }
 }
 
 impl SetAttributes for Common {
@@ -266,15 +274,7 @@ impl SetAttributes for Common {
 
                 expanded_name!("", "kernelUnitLength") => {
                     let NumberOptionalNumber(x, y) =
-                        attr.parse_and_validate(value, |v: NumberOptionalNumber<f64>| {
-                            if v.0 > 0.0 && v.1 > 0.0 {
-                                Ok(v)
-                            } else {
-                                Err(ValueErrorKind::value_error(
-                                    "kernelUnitLength can't be less or equal to zero",
-                                ))
-                            }
-                        })?;
+                        attr.parse_and_validate(value, check_kernel_unit_length)?;
 
                     self.kernel_unit_length = Some((x, y));
                 }
@@ -286,6 +286,30 @@ impl SetAttributes for Common {
     }
 }
 
+/// Validates a `diffuseConstant` or `specularConstant`, which can't be negative.
+fn check_constant(attr_name: &str, x: f64) -> Result<f64, ValueErrorKind> {
+    if x >= 0.0 {
+        Ok(x)
+    } else {
+        Err(ValueErrorKind::value_error(&format!(
+            "{} {} can't be negative",
+            attr_name, x
+        )))
+    }
+}
+
+/// Validates a `specularExponent`, which must be in the range 1.0 to 128.0.
+fn check_specular_exponent(x: f64) -> Result<f64, ValueErrorKind> {
+    if (1.0..=128.0).contains(&x) {
+        Ok(x)
+    } else {
+        Err(ValueErrorKind::value_error(&format!(
+            "specularExponent {} is outside 1.0..128.0",
+            x
+        )))
+    }
+}
+
 /// The `feDiffuseLighting` filter primitives.
 pub struct FeDiffuseLighting {
     common: Common,
@@ -308,16 +332,8 @@ impl SetAttributes for FeDiffuseLighting {
             .iter()
             .find(|(attr, _)| attr.expanded() == expanded_name!("", "diffuseConstant"))
             .and_then(|(attr, value)| {
-                attr.parse_and_validate(value, |x| {
-                    if x >= 0.0 {
-                        Ok(x)
-                    } else {
-                        Err(ValueErrorKind::value_error(
-                            "diffuseConstant can't be negative",
-                        ))
-                    }
-                })
-                .ok()
+                attr.parse_and_validate(value, |x| check_constant("diffuseConstant", x))
+                    .ok()
             });
         if let Some(diffuse_constant) = result {
             self.diffuse_constant = diffuse_constant;
@@ -335,7 +351,7 @@ impl Lighting for FeDiffuseLighting {
 
     #[inline]
     fn compute_factor(&self, normal: Normal, light_vector: Vector3<f64>) -> f64 {
-        let k = if normal.normal.is_zero() {
+        let k = if normal.normal.is_zero() || self.common().surface_is_flat() {
             // Common case of (0, 0, 1) normal.
             light_vector.z
         } else {
@@ -376,26 +392,12 @@ impl SetAttributes for FeSpecularLighting {
         for (attr, value) in pbag.iter() {
             match attr.expanded() {
                 expanded_name!("", "specularConstant") => {
-                    self.specular_constant = attr.parse_and_validate(value, |x| {
-                        if x >= 0.0 {
-                            Ok(x)
-                        } else {
-                            Err(ValueErrorKind::value_error(
-                                "specularConstant can't be negative",
-                            ))
-                        }
-                    })?;
+                    self.specular_constant =
+                        attr.parse_and_validate(value, |x| check_constant("specularConstant", x))?;
                 }
                 expanded_name!("", "specularExponent") => {
-                    self.specular_exponent = attr.parse_and_validate(value, |x| {
-                        if x >= 1.0 && x <= 128.0 {
-                            Ok(x)
-                        } else {
-                            Err(ValueErrorKind::value_error(
-                                "specularExponent should be between 1.0 and 128.0",
-                            ))
-                        }
-                    })?;
+                    self.specular_exponent =
+                        attr.parse_and_validate(value, check_specular_exponent)?;
                 }
                 _ => (),
             }
@@ -420,7 +422,7 @@ impl Lighting for FeSpecularLighting {
             return 0.0;
         }
 
-        let k = if normal.normal.is_zero() {
+        let k = if normal.normal.is_zero() || self.common().surface_is_flat() {
             // Common case of (0, 0, 1) normal.
             let n_dot_h = h.z / h_norm;
             if approx_eq!(f64, self.specular_exponent, 1.0) {
@@ -471,17 +473,33 @@ macro_rules! impl_lighting_filter {
                     .into_irect(draw_ctx);
                 let original_bounds = bounds;
 
+                let transparent_result = |bounds| -> Result<FilterResult, FilterError> {
+                    let surface = SharedImageSurface::empty(
+                        ctx.source_graphic().width(),
+                        ctx.source_graphic().height(),
+                        input.surface().surface_type(),
+                    )?;
+
+                    Ok(FilterResult {
+                        name: self.common().base.result.clone(),
+                        output: FilterOutput { surface, bounds },
+                    })
+                };
+
+                // An empty primitive subregion renders nothing, which is not an error.
+                if bounds.is_empty() {
+                    return transparent_result(bounds);
+                }
+
                 let scale = self
                     .common()
                     .kernel_unit_length
-                    .map(|(dx, dy)| ctx.paffine().transform_distance(dx, dy));
+                    .map(|(dx, dy)| kernel_unit_length_scale(ctx.paffine(), dx, dy))
+                    .transpose()?;
 
                 let cascaded = CascadedValues::new_from_node(node);
                 let values = cascaded.get();
-                let lighting_color = match values.lighting_color().0 {
-                    cssparser::Color::CurrentColor => values.color().0,
-                    cssparser::Color::RGBA(rgba) => rgba,
-                };
+                let lighting_color = resolve_color(values.lighting_color().0, values.color().0);
 
                 let light_source = find_light_source(node, ctx)?;
                 let mut input_surface = input.surface().clone();
@@ -497,10 +515,16 @@ macro_rules! impl_lighting_filter {
 
                 let (bounds_w, bounds_h) = bounds.size();
 
-                // Check if the surface is too small for normal computation. This case is
-                // unspecified; WebKit doesn't render anything in this case.
+                // The surface normals need at least 2×2 pixels. This case is unspecified;
+                // WebKit doesn't render anything, so we produce a transparent result and let
+                // the rest of the filter chain continue.
                 if bounds_w < 2 || bounds_h < 2 {
-                    return Err(FilterError::LightingInputTooSmall);
+                    rsvg_log!(
+                        "(lighting input of {}×{} pixels is too small; rendering nothing)",
+                        bounds_w,
+                        bounds_h
+                    );
+                    return transparent_result(original_bounds);
                 }
 
                 let (ox, oy) = scale.unwrap_or((1.0, 1.0));
@@ -680,6 +704,10 @@ macro_rules! impl_lighting_filter {
             fn is_affected_by_color_interpolation_filters(&self) -> bool {
                 true
             }
+
+            fn base(&self) -> &dyn PrimitiveBase {
+                &self.common().base
+            }
         }
     };
 }
@@ -695,22 +723,48 @@ fn specular_alpha(r: u8, g: u8, b: u8) -> u8 {
 impl_lighting_filter!(FeDiffuseLighting, diffuse_alpha);
 impl_lighting_filter!(FeSpecularLighting, specular_alpha);
 
-fn find_light_source(node: &Node, ctx: &FilterContext) -> Result<LightSource, FilterError> {
-    let mut light_sources = node.children().rev().filter(|c| {
-        c.is_element() && matches!(*c.borrow_element(), Element::FeDistantLight(_) | Element::FePointLight(_) | Element::FeSpotLight(_))
-    });
-
-    let node = light_sources.next();
-    if node.is_none() || light_sources.next().is_some() {
-        return Err(FilterError::InvalidLightSourceCount);
+/// Returns the single light source child of a lighting primitive.
+///
+/// Fails if there isn't exactly one light source, or if it is in error.
+fn find_light_source_node(node: &Node) -> Result<Node, FilterError> {
+    let light_source = node
+        .exactly_one_child(|e| {
+            matches!(
+                *e,
+                Element::FeDistantLight(_) | Element::FePointLight(_) | Element::FeSpotLight(_)
+            )
+        })
+        .map_err(FilterError::InvalidLightSourceCount)?;
+
+    if light_source.borrow_element().is_in_error() {
+        return Err(FilterError::ChildNodeInError);
     }
 
-    let node = node.unwrap();
-    let elt = node.borrow_element();
+    Ok(light_source)
+}
 
-    if elt.is_in_error() {
-        return Err(FilterError::ChildNodeInError);
+/// Transforms `kernelUnitLength` to device space, for scaling the input surface.
+///
+/// A transform that flips an axis still yields a positive size.  Fails if the size is
+/// zero or not finite, for example when the transform collapses an axis.
+fn kernel_unit_length_scale(
+    paffine: Transform,
+    dx: f64,
+    dy: f64,
+) -> Result<(f64, f64), FilterError> {
+    let (ox, oy) = paffine.transform_distance(dx, dy);
+    let (ox, oy) = (ox.abs(), oy.abs());
+
+    if ox.is_finite() && oy.is_finite() && ox > 0.0 && oy > 0.0 {
+        Ok((ox, oy))
+    } else {
+        Err(FilterError::InvalidKernelUnitLength)
     }
+}
+
+fn find_light_source(node: &Node, ctx: &FilterContext) -> Result<LightSource, FilterError> {
+    let node = find_light_source_node(node)?;
+    let elt = node.borrow_element();
 
     let light_source = match *elt {
         Element::FeDistantLight(ref l) => l.transform(ctx),
@@ -733,6 +787,14 @@ pub struct Normal {
     pub normal: Vector2<i16>,
 }
 
+/// Returns the alpha value at the given coordinates for the edge normals.
+///
+/// Pixels outside the surface count as transparent.
+#[inline]
+fn edge_alpha(surface: &SharedImageSurface, x: u32, y: u32) -> i16 {
+    surface.try_get_pixel(x, y).map_or(0, |p| i16::from(p.a))
+}
+
 impl Normal {
     #[inline]
     fn new(factor_x: f64, nx: i16, factor_y: f64, ny: i16) -> Normal {
@@ -750,7 +812,7 @@ impl Normal {
         assert!(bounds.width() >= 2);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let (x, y) = (bounds.x0 as u32, bounds.y0 as u32);
 
         let center = get(x, y);
@@ -773,7 +835,7 @@ impl Normal {
         assert!((x as i32) + 1 < bounds.x1);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let y = bounds.y0 as u32;
 
         let left = get(x - 1, y);
@@ -798,7 +860,7 @@ impl Normal {
         assert!(bounds.width() >= 2);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let (x, y) = (bounds.x1 as u32 - 1, bounds.y0 as u32);
 
         let left = get(x - 1, y);
@@ -821,7 +883,7 @@ impl Normal {
         assert!((y as i32) + 1 < bounds.y1);
         assert!(bounds.width() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let x = bounds.x0 as u32;
 
         let top = get(x, y - 1);
@@ -873,7 +935,7 @@ impl Normal {
         assert!((y as i32) + 1 < bounds.y1);
         assert!(bounds.width() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let x = bounds.x1 as u32 - 1;
 
         let top_left = get(x - 1, y - 1);
@@ -898,7 +960,7 @@ impl Normal {
         assert!(bounds.width() >= 2);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let (x, y) = (bounds.x0 as u32, bounds.y1 as u32 - 1);
 
         let top = get(x, y - 1);
@@ -921,7 +983,7 @@ impl Normal {
         assert!((x as i32) + 1 < bounds.x1);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let y = bounds.y1 as u32 - 1;
 
         let top_left = get(x - 1, y - 1);
@@ -946,7 +1008,7 @@ impl Normal {
         assert!(bounds.width() >= 2);
         assert!(bounds.height() >= 2);
 
-        let get = |x, y| i16::from(surface.get_pixel(x, y).a);
+        let get = |x, y| edge_alpha(surface, x, y);
         let (x, y) = (bounds.x1 as u32 - 1, bounds.y1 as u32 - 1);
 
         let top_left = get(x - 1, y - 1);
@@ -962,3 +1024,121 @@ impl Normal {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matches::matches;
+
+    use crate::document::load_document;
+    use crate::error::ChildCountError;
+
+    #[test]
+    fn flat_surface_uses_upward_normal() {
+        let mut diffuse = FeDiffuseLighting::default();
+        diffuse.common.surface_scale = 0.0;
+
+        let mut specular = FeSpecularLighting::default();
+        specular.common.surface_scale = 0.0;
+        specular.specular_exponent = 2.5;
+
+        let normal = Normal::new(1.0 / 4.0, 255, 1.0 / 4.0, -255);
+
+        for light_vector in &[
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.6, 0.0, 0.8),
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::zeros(),
+        ] {
+            let k = diffuse.compute_factor(normal, *light_vector);
+            assert!(k.is_finite());
+            assert!(approx_eq!(f64, k, light_vector.z));
+
+            let k = specular.compute_factor(normal, *light_vector);
+            assert!(k.is_finite());
+            assert!(k >= 0.0);
+        }
+    }
+
+    #[test]
+    fn validates_light_source_child() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feDiffuseLighting id="valid">
+      <feFlood/>
+      <fePointLight id="light" x="1" y="2" z="3"/>
+    </feDiffuseLighting>
+    <feDiffuseLighting id="in-error">
+      <fePointLight x="foo"/>
+    </feDiffuseLighting>
+    <feSpecularLighting id="missing">
+      <feFlood/>
+    </feSpecularLighting>
+  </filter>
+</svg>
+"#,
+        );
+
+        let valid = document.lookup_node_by_id("valid").unwrap();
+        let light = document.lookup_node_by_id("light").unwrap();
+        assert!(find_light_source_node(&valid).unwrap() == light);
+
+        let in_error = document.lookup_node_by_id("in-error").unwrap();
+        assert!(matches!(
+            find_light_source_node(&in_error),
+            Err(FilterError::ChildNodeInError)
+        ));
+
+        let missing = document.lookup_node_by_id("missing").unwrap();
+        assert!(matches!(
+            find_light_source_node(&missing),
+            Err(FilterError::InvalidLightSourceCount(
+                ChildCountError::NoChildren
+            ))
+        ));
+    }
+
+    #[test]
+    fn range_errors_mention_the_value() {
+        assert_eq!(check_specular_exponent(20.0), Ok(20.0));
+        assert_eq!(
+            check_specular_exponent(200.0),
+            Err(ValueErrorKind::Value(String::from(
+                "specularExponent 200 is outside 1.0..128.0"
+            )))
+        );
+
+        assert_eq!(check_constant("specularConstant", 0.0), Ok(0.0));
+        assert_eq!(
+            check_constant("specularConstant", -1.5),
+            Err(ValueErrorKind::Value(String::from(
+                "specularConstant -1.5 can't be negative"
+            )))
+        );
+    }
+
+    #[test]
+    fn kernel_unit_length_must_stay_nonzero() {
+        assert_eq!(
+            kernel_unit_length_scale(Transform::new_scale(2.0, 3.0), 1.0, 2.0),
+            Ok((2.0, 6.0))
+        );
+
+        assert_eq!(
+            kernel_unit_length_scale(Transform::new_scale(-1.0, 1.0), 1.0, 2.0),
+            Ok((1.0, 2.0))
+        );
+
+        assert_eq!(
+            kernel_unit_length_scale(Transform::new_scale(1.0, 0.0), 1.0, 1.0),
+            Err(FilterError::InvalidKernelUnitLength)
+        );
+
+        assert_eq!(
+            kernel_unit_length_scale(Transform::new_scale(f64::INFINITY, 1.0), 1.0, 1.0),
+            Err(FilterError::InvalidKernelUnitLength)
+        );
+    }
+}
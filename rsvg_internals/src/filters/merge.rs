@@ -10,7 +10,7 @@ use crate::rect::IRect;
 use crate::surface_utils::shared_surface::{SharedImageSurface, SurfaceType};
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Input, Primitive};
+use super::{FilterEffect, FilterError, Input, Primitive, PrimitiveBase};
 
 /// The `feMerge` filter primitive.
 pub struct FeMerge {
@@ -84,7 +84,7 @@ impl FilterEffect for FeMerge {
         draw_ctx: &mut DrawingCtx,
     ) -> Result<FilterResult, FilterError> {
         // Compute the filter bounds, taking each child node's input into account.
-        let mut bounds = self.base.get_bounds(ctx, node.parent().as_ref())?;
+        let mut inputs = Vec::new();
         for child in node.children().filter(|c| c.is_element()) {
             let elt = child.borrow_element();
 
@@ -93,12 +93,15 @@ impl FilterEffect for FeMerge {
             }
 
             if let Element::FeMergeNode(ref merge_node) = *elt {
-                let input = ctx.get_input(acquired_nodes, draw_ctx, merge_node.in_.as_ref())?;
-                bounds = bounds.add_input(&input);
+                inputs.push(ctx.get_input(acquired_nodes, draw_ctx, merge_node.in_.as_ref())?);
             }
         }
 
-        let bounds = bounds.into_irect(draw_ctx);
+        let bounds = self
+            .base
+            .get_bounds(ctx, node.parent().as_ref())?
+            .add_inputs(&inputs)
+            .into_irect(draw_ctx);
 
         // Now merge them all.
         let mut output_surface = None;
@@ -133,4 +136,52 @@ impl FilterEffect for FeMerge {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::document::load_document;
+    use crate::parsers::{CustomIdent, Parse};
+
+    #[test]
+    fn extracts_merge_nodes() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter id="filter">
+    <feMerge id="merge">
+      <feMergeNode in="SourceGraphic"/>
+      <feMergeNode in="blur"/>
+    </feMerge>
+  </filter>
+</svg>
+"#,
+        );
+
+        let merge = document.lookup_node_by_id("merge").unwrap();
+        assert!(is_element_of_type!(merge, FeMerge));
+
+        let inputs = merge
+            .children()
+            .filter(|c| c.is_element())
+            .map(|c| match *c.borrow_element() {
+                Element::FeMergeNode(ref merge_node) => merge_node.in_.clone(),
+                _ => panic!("expected only feMergeNode children"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            inputs,
+            vec![
+                Some(Input::SourceGraphic),
+                Some(Input::FilterOutput(CustomIdent::parse_str("blur").unwrap())),
+            ]
+        );
+    }
 }
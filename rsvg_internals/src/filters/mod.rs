@@ -1,23 +1,28 @@
 //! Entry point for the CSS filters infrastructure.
 
-use cssparser::{BasicParseError, Parser};
+use cssparser::{BasicParseError, Parser, Token};
 use markup5ever::{expanded_name, local_name, namespace_url, ns};
+use std::fmt;
 use std::ops::Deref;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::bbox::BoundingBox;
 use crate::coord_units::CoordUnits;
 use crate::document::AcquiredNodes;
 use crate::drawing_ctx::DrawingCtx;
 use crate::element::{Draw, Element, ElementResult, SetAttributes};
-use crate::error::{ParseError, RenderingError};
+use crate::error::{ParseError, RenderingError, ValueErrorKind};
 use crate::length::*;
+use crate::log;
 use crate::node::{CascadedValues, Node, NodeBorrow};
-use crate::parsers::{CustomIdent, Parse, ParseValue};
+use crate::parsers::{CustomIdent, NumberOptionalNumber, Parse, ParseValue};
 use crate::properties::ComputedValues;
 use crate::property_bag::PropertyBag;
 use crate::property_defs::ColorInterpolationFilters;
-use crate::surface_utils::shared_surface::{SharedImageSurface, SurfaceType};
+use crate::surface_utils::{
+    shared_surface::{SharedImageSurface, SurfaceType},
+    EdgeMode, Pixel,
+};
 use crate::transform::Transform;
 
 mod bounds;
@@ -49,6 +54,31 @@ pub trait FilterEffect: SetAttributes + Draw {
     /// Primitives that do color blending (like `feComposite` or `feBlend`) should return `true`
     /// here, whereas primitives that don't (like `feOffset`) should return `false`.
     fn is_affected_by_color_interpolation_filters(&self) -> bool;
+
+    /// Returns the attributes that this filter primitive shares with all the others.
+    fn base(&self) -> &dyn PrimitiveBase;
+
+    /// Returns the input, result name and subregion of this filter primitive, for diagnostics.
+    fn describe(&self, node: &Node, ctx: &FilterContext, draw_ctx: &mut DrawingCtx) -> String {
+        self.base().describe(node, ctx, draw_ctx)
+    }
+
+    /// Returns the subregion of this filter primitive as specified by its attributes.
+    ///
+    /// This is the unresolved `x`, `y`, `width` and `height`, before any defaults are applied
+    /// or the subregion is clipped to the filter effects region.
+    fn subregion(&self) -> PrimitiveSubregion {
+        self.base().subregion()
+    }
+}
+
+/// The attributes common to filter primitives, with or without an `in` attribute.
+pub trait PrimitiveBase {
+    /// Returns the subregion attributes of this primitive.
+    fn subregion(&self) -> PrimitiveSubregion;
+
+    /// Describes the input, if any, result name and subregion of this primitive.
+    fn describe(&self, node: &Node, ctx: &FilterContext, draw_ctx: &mut DrawingCtx) -> String;
 }
 
 // Filter Effects do not need to draw themselves
@@ -79,6 +109,17 @@ struct Primitive {
     result: Option<CustomIdent>,
 }
 
+/// The subregion attributes of a filter primitive, as specified in the document.
+///
+/// Each field is `None` if the corresponding attribute was not given.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrimitiveSubregion {
+    pub x: Option<Length<Horizontal>>,
+    pub y: Option<Length<Vertical>>,
+    pub width: Option<Length<Horizontal>>,
+    pub height: Option<Length<Vertical>>,
+}
+
 /// An enumeration of possible inputs for a filter primitive.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Input {
@@ -91,19 +132,43 @@ pub enum Input {
     FilterOutput(CustomIdent),
 }
 
+/// The keywords for the standard inputs, and the inputs they name.
+const WELL_KNOWN_INPUTS: [(&str, Input); 6] = [
+    ("SourceGraphic", Input::SourceGraphic),
+    ("SourceAlpha", Input::SourceAlpha),
+    ("BackgroundImage", Input::BackgroundImage),
+    ("BackgroundAlpha", Input::BackgroundAlpha),
+    ("FillPaint", Input::FillPaint),
+    ("StrokePaint", Input::StrokePaint),
+];
+
+impl Input {
+    /// Returns the keywords for the standard inputs, like `SourceGraphic`.
+    ///
+    /// Any other name in an `in` or `in2` attribute refers to the `result` of a previous
+    /// filter primitive.
+    pub fn well_known_names() -> impl Iterator<Item = &'static str> {
+        WELL_KNOWN_INPUTS.iter().map(|(name, _)| *name)
+    }
+}
+
 impl Parse for Input {
     fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
         parser
             .try_parse(|p| {
-                Ok(parse_identifiers!(
-                    p,
-                    "SourceGraphic" => Input::SourceGraphic,
-                    "SourceAlpha" => Input::SourceAlpha,
-                    "BackgroundImage" => Input::BackgroundImage,
-                    "BackgroundAlpha" => Input::BackgroundAlpha,
-                    "FillPaint" => Input::FillPaint,
-                    "StrokePaint" => Input::StrokePaint,
-                )?)
+                let loc = p.current_source_location();
+                let token = p.next()?;
+
+                if let Token::Ident(ref cow) = token {
+                    if let Some((_, input)) = WELL_KNOWN_INPUTS
+                        .iter()
+                        .find(|(name, _)| cow.eq_ignore_ascii_case(name))
+                    {
+                        return Ok(input.clone());
+                    }
+                }
+
+                Err(loc.new_basic_unexpected_token_error(token.clone()))
             })
             .or_else(|_: BasicParseError| {
                 let ident = CustomIdent::parse(parser)?;
@@ -112,6 +177,21 @@ impl Parse for Input {
     }
 }
 
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Input::FilterOutput(ref ident) => write!(f, "{}", ident.as_str()),
+            ref input => {
+                let (name, _) = WELL_KNOWN_INPUTS
+                    .iter()
+                    .find(|(_, i)| i == input)
+                    .expect("all standard inputs have a keyword");
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
 /// The base node for filter primitives which accept input.
 struct PrimitiveWithInput {
     base: Primitive,
@@ -199,6 +279,28 @@ impl Primitive {
     }
 }
 
+impl PrimitiveBase for Primitive {
+    fn subregion(&self) -> PrimitiveSubregion {
+        PrimitiveSubregion {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    fn describe(&self, node: &Node, ctx: &FilterContext, draw_ctx: &mut DrawingCtx) -> String {
+        let result = self.result.as_ref().map_or("(none)", CustomIdent::as_str);
+
+        let subregion = match self.get_bounds(ctx, node.parent().as_ref()) {
+            Ok(bounds) => format!("{:?}", bounds.into_irect(draw_ctx)),
+            Err(err) => format!("({})", err),
+        };
+
+        format!("result={} subregion={}", result, subregion)
+    }
+}
+
 impl SetAttributes for Primitive {
     fn set_attributes(&mut self, pbag: &PropertyBag<'_>) -> ElementResult {
         for (attr, value) in pbag.iter() {
@@ -245,6 +347,21 @@ impl PrimitiveWithInput {
     }
 }
 
+impl PrimitiveBase for PrimitiveWithInput {
+    fn subregion(&self) -> PrimitiveSubregion {
+        self.base.subregion()
+    }
+
+    fn describe(&self, node: &Node, ctx: &FilterContext, draw_ctx: &mut DrawingCtx) -> String {
+        let input = match self.in_ {
+            None => String::from("(previous)"),
+            Some(ref input) => input.to_string(),
+        };
+
+        format!("in={} {}", input, self.base.describe(node, ctx, draw_ctx))
+    }
+}
+
 impl SetAttributes for PrimitiveWithInput {
     fn set_attributes(&mut self, pbag: &PropertyBag<'_>) -> ElementResult {
         self.base.set_attributes(pbag)?;
@@ -291,7 +408,7 @@ pub fn render(
         draw_ctx,
         transform,
         node_bbox,
-    );
+    )?;
 
     // If paffine is non-invertible, we won't draw anything. Also bbox combining in bounds
     // computations will panic due to non-invertible martrix.
@@ -299,6 +416,12 @@ pub fn render(
         return Ok(filter_ctx.into_output()?);
     }
 
+    rsvg_log!(
+        "(filter chain for {}:\n{})",
+        filter_node,
+        filter_ctx.describe_chain(draw_ctx)
+    );
+
     let primitives = filter_node
         .children()
         .filter(|c| c.is_element())
@@ -346,7 +469,12 @@ pub fn render(
             Ok(())
         };
 
-        let start = Instant::now();
+        // Only look at the clock if the timing is going to be logged.
+        let start = if log::log_enabled() {
+            Some(Instant::now())
+        } else {
+            None
+        };
 
         if filter.is_affected_by_color_interpolation_filters() && linear_rgb {
             filter_ctx.with_linear_rgb(render)?;
@@ -354,17 +482,107 @@ pub fn render(
             render(&mut filter_ctx)?;
         }
 
-        let elapsed = start.elapsed();
-        rsvg_log!(
-            "(rendered filter primitive {} in\n    {} seconds)",
-            c,
-            elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9
-        );
+        if let Some(start) = start {
+            rsvg_log!("{}", timing_message(&c, start.elapsed()));
+        }
     }
 
     Ok(filter_ctx.into_output()?)
 }
 
+/// Formats the log message with the time it took to render a filter primitive.
+fn timing_message(node: &Node, elapsed: Duration) -> String {
+    format!(
+        "(rendered filter primitive {} in {} microseconds)",
+        node,
+        elapsed.as_micros()
+    )
+}
+
+/// Resolves a color property of a filter primitive, like `flood-color` or `lighting-color`.
+///
+/// `current` is the value of the `color` property, used for `currentColor`.
+fn resolve_color(color: cssparser::Color, current: cssparser::RGBA) -> cssparser::RGBA {
+    match color {
+        cssparser::Color::CurrentColor => current,
+        cssparser::Color::RGBA(rgba) => rgba,
+    }
+}
+
+/// Validates a `kernelUnitLength`, whose components must both be greater than zero.
+///
+/// The error says which component was wrong, since with a single number the other one is
+/// just a copy of it.
+fn check_kernel_unit_length(
+    v: NumberOptionalNumber<f64>,
+) -> Result<NumberOptionalNumber<f64>, ValueErrorKind> {
+    let NumberOptionalNumber(x, y) = v;
+
+    match (x > 0.0, y > 0.0) {
+        (true, true) => Ok(v),
+        (false, false) => Err(ValueErrorKind::value_error(
+            "kernelUnitLength must be greater than zero",
+        )),
+        (false, true) => Err(ValueErrorKind::value_error(&format!(
+            "the x value {} of kernelUnitLength must be greater than zero",
+            x
+        ))),
+        (true, false) => Err(ValueErrorKind::value_error(&format!(
+            "the y value {} of kernelUnitLength must be greater than zero",
+            y
+        ))),
+    }
+}
+
+/// A color channel of a pixel.
+///
+/// Used for the `xChannelSelector` and `yChannelSelector` attributes of `feDisplacementMap`,
+/// and to tell apart the `feFuncX` children of `feComponentTransfer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+impl Channel {
+    /// Returns the value of this channel in `pixel`.
+    #[inline]
+    fn get(self, pixel: Pixel) -> u8 {
+        match self {
+            Channel::R => pixel.r,
+            Channel::G => pixel.g,
+            Channel::B => pixel.b,
+            Channel::A => pixel.a,
+        }
+    }
+}
+
+impl Parse for Channel {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "R" => Channel::R,
+            "G" => Channel::G,
+            "B" => Channel::B,
+            "A" => Channel::A,
+        )?)
+    }
+}
+
+// Used for the edgeMode attribute of feConvolveMatrix and feGaussianBlur
+impl Parse for EdgeMode {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
+        Ok(parse_identifiers!(
+            parser,
+            "duplicate" => EdgeMode::Duplicate,
+            "wrap" => EdgeMode::Wrap,
+            "none" => EdgeMode::None,
+        )?)
+    }
+}
+
 impl From<ColorInterpolationFilters> for SurfaceType {
     fn from(c: ColorInterpolationFilters) -> Self {
         match c {
@@ -373,3 +591,180 @@ impl From<ColorInterpolationFilters> for SurfaceType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use markup5ever::QualName;
+    use matches::matches;
+    use std::ptr;
+
+    use crate::document::load_document;
+    use crate::node::NodeData;
+
+    #[test]
+    fn timing_message_names_primitive() {
+        let bag = unsafe { PropertyBag::new_from_xml2_attributes(0, ptr::null()) };
+        let node = Node::new(NodeData::new_element(
+            &QualName::new(None, ns!(svg), local_name!("feComposite")),
+            &bag,
+        ));
+
+        let message = timing_message(&node, Duration::from_micros(1500));
+        assert!(message.contains("feComposite"));
+        assert!(message.contains("1500 microseconds"));
+    }
+
+    #[test]
+    fn exposes_specified_subregion() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feFlood id="flood" x="10%" y="10%" width="80%" height="80%"/>
+    <feOffset id="offset" y="2mm"/>
+    <feMerge id="merge"/>
+  </filter>
+</svg>
+"#,
+        );
+
+        let subregion = |id| {
+            let node = document.lookup_node_by_id(id).unwrap();
+            let elt = node.borrow_element();
+            elt.as_filter_effect().unwrap().subregion()
+        };
+
+        assert_eq!(
+            subregion("flood"),
+            PrimitiveSubregion {
+                x: Some(Length::new(0.1, LengthUnit::Percent)),
+                y: Some(Length::new(0.1, LengthUnit::Percent)),
+                width: Some(Length::new(0.8, LengthUnit::Percent)),
+                height: Some(Length::new(0.8, LengthUnit::Percent)),
+            }
+        );
+
+        assert_eq!(
+            subregion("offset"),
+            PrimitiveSubregion {
+                x: None,
+                y: Some(Length::new(2.0, LengthUnit::Mm)),
+                width: None,
+                height: None,
+            }
+        );
+
+        assert_eq!(
+            subregion("merge"),
+            PrimitiveSubregion {
+                x: None,
+                y: None,
+                width: None,
+                height: None,
+            }
+        );
+    }
+
+    #[test]
+    fn resolves_color() {
+        let current = cssparser::RGBA::new(1, 2, 3, 4);
+        let explicit = cssparser::RGBA::new(5, 6, 7, 8);
+
+        assert_eq!(
+            resolve_color(cssparser::Color::CurrentColor, current),
+            current
+        );
+        assert_eq!(
+            resolve_color(cssparser::Color::RGBA(explicit), current),
+            explicit
+        );
+    }
+
+    #[test]
+    fn well_known_inputs_round_trip() {
+        let names: Vec<&str> = Input::well_known_names().collect();
+        assert_eq!(names.len(), 6);
+
+        for name in names {
+            let input = Input::parse_str(name).unwrap();
+            assert!(!matches!(input, Input::FilterOutput(_)));
+            assert_eq!(input.to_string(), name);
+        }
+
+        let input = Input::parse_str("blur").unwrap();
+        assert_eq!(
+            input,
+            Input::FilterOutput(CustomIdent::parse_str("blur").unwrap())
+        );
+        assert_eq!(input.to_string(), "blur");
+    }
+
+    #[test]
+    fn kernel_unit_length_errors_name_the_component() {
+        let check = |s| check_kernel_unit_length(NumberOptionalNumber::parse_str(s).unwrap());
+
+        assert_eq!(check("1 2"), Ok(NumberOptionalNumber(1.0, 2.0)));
+
+        assert_eq!(
+            check("0 1"),
+            Err(ValueErrorKind::Value(String::from(
+                "the x value 0 of kernelUnitLength must be greater than zero"
+            )))
+        );
+        assert_eq!(
+            check("1 0"),
+            Err(ValueErrorKind::Value(String::from(
+                "the y value 0 of kernelUnitLength must be greater than zero"
+            )))
+        );
+        assert_eq!(
+            check("-1"),
+            Err(ValueErrorKind::Value(String::from(
+                "kernelUnitLength must be greater than zero"
+            )))
+        );
+    }
+
+    #[test]
+    fn parses_channel() {
+        assert_eq!(Channel::parse_str("R"), Ok(Channel::R));
+        assert_eq!(Channel::parse_str("G"), Ok(Channel::G));
+        assert_eq!(Channel::parse_str("B"), Ok(Channel::B));
+        assert_eq!(Channel::parse_str("A"), Ok(Channel::A));
+
+        // parse_identifiers!() matches case-insensitively.
+        assert_eq!(Channel::parse_str("r"), Ok(Channel::R));
+
+        assert!(Channel::parse_str("X").is_err());
+    }
+
+    #[test]
+    fn channel_selects_pixel_component() {
+        let pixel = Pixel {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        };
+
+        assert_eq!(Channel::R.get(pixel), 1);
+        assert_eq!(Channel::G.get(pixel), 2);
+        assert_eq!(Channel::B.get(pixel), 3);
+        assert_eq!(Channel::A.get(pixel), 4);
+    }
+
+    #[test]
+    fn parses_edge_mode() {
+        assert_eq!(EdgeMode::parse_str("duplicate"), Ok(EdgeMode::Duplicate));
+        assert_eq!(EdgeMode::parse_str("wrap"), Ok(EdgeMode::Wrap));
+        assert_eq!(EdgeMode::parse_str("none"), Ok(EdgeMode::None));
+    }
+
+    #[test]
+    fn invalid_edge_mode_yields_error() {
+        assert!(EdgeMode::parse_str("").is_err());
+        assert!(EdgeMode::parse_str("mirror").is_err());
+        assert!(EdgeMode::parse_str("duplicate wrap").is_err());
+    }
+}
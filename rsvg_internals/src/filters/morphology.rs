@@ -18,7 +18,7 @@ use crate::surface_utils::{
 };
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// Enumeration of the possible morphology operations.
 enum Operator {
@@ -153,6 +153,10 @@ impl FilterEffect for FeMorphology {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         false
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
 
 impl Parse for Operator {
@@ -6,9 +6,10 @@ use crate::element::{ElementResult, SetAttributes};
 use crate::node::Node;
 use crate::parsers::ParseValue;
 use crate::property_bag::PropertyBag;
+use crate::surface_utils::OffsetMode;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// The `feOffset` filter primitive.
 pub struct FeOffset {
@@ -62,7 +63,10 @@ impl FilterEffect for FeOffset {
 
         let (dx, dy) = ctx.paffine().transform_distance(self.dx, self.dy);
 
-        let surface = input.surface().offset(bounds, dx, dy)?;
+        // Sub-pixel offsets are applied exactly; see the documentation for `OffsetMode`.
+        let surface = input
+            .surface()
+            .offset(bounds, dx, dy, OffsetMode::default())?;
 
         Ok(FilterResult {
             name: self.base.result.clone(),
@@ -74,4 +78,8 @@ impl FilterEffect for FeOffset {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         false
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
@@ -5,7 +5,7 @@ use crate::node::Node;
 use crate::property_bag::PropertyBag;
 
 use super::context::{FilterContext, FilterInput, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, PrimitiveWithInput};
+use super::{FilterEffect, FilterError, PrimitiveBase, PrimitiveWithInput};
 
 /// The `feTile` filter primitive.
 pub struct FeTile {
@@ -71,4 +71,8 @@ impl FilterEffect for FeTile {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         false
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
@@ -15,7 +15,7 @@ use crate::surface_utils::{
 use crate::util::clamp;
 
 use super::context::{FilterContext, FilterOutput, FilterResult};
-use super::{FilterEffect, FilterError, Primitive};
+use super::{FilterEffect, FilterError, Primitive, PrimitiveBase};
 
 /// Enumeration of the tile stitching modes.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -416,6 +416,10 @@ impl FilterEffect for FeTurbulence {
     fn is_affected_by_color_interpolation_filters(&self) -> bool {
         true
     }
+
+    fn base(&self) -> &dyn PrimitiveBase {
+        &self.base
+    }
 }
 
 impl Parse for StitchTiles {
@@ -192,6 +192,7 @@ impl FontSize {
             parent.unit != LengthUnit::Percent
                 && parent.unit != LengthUnit::Em
                 && parent.unit != LengthUnit::Ex
+                && parent.unit != LengthUnit::Ch
         );
 
         use FontSize::*;
@@ -222,6 +223,11 @@ impl FontSize {
                 Length::<Both>::new(parent.length * s.length / 2.0, parent.unit)
             }
 
+            Value(s) if s.unit == LengthUnit::Ch => {
+                // FIXME: this should be the advance of the "0" glyph.
+                Length::<Both>::new(parent.length * s.length / 2.0, parent.unit)
+            }
+
             Value(s) => *s,
         };
 
@@ -500,6 +506,8 @@ impl FontFamily {
 mod tests {
     use super::*;
 
+    use crate::dpi::Dpi;
+    use crate::float_eq_cairo::ApproxEqCairo;
     use crate::properties::{ParsedProperty, SpecifiedValue, SpecifiedValues};
 
     #[test]
@@ -536,6 +544,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_relative_and_named_font_sizes() {
+        assert_eq!(FontSize::parse_str("larger"), Ok(FontSize::Larger));
+        assert_eq!(FontSize::parse_str("smaller"), Ok(FontSize::Smaller));
+        assert_eq!(FontSize::parse_str("xx-small"), Ok(FontSize::XXSmall));
+        assert_eq!(FontSize::parse_str("medium"), Ok(FontSize::Medium));
+        assert_eq!(FontSize::parse_str("xx-large"), Ok(FontSize::XXLarge));
+    }
+
     #[test]
     fn detects_invalid_invalid_font_size() {
         assert!(FontSize::parse_str("furlong").is_err());
@@ -583,6 +600,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn normalizes_larger_and_smaller_from_parent_font_size() {
+        let mut specified = SpecifiedValues::default();
+        specified.set_parsed_property(&ParsedProperty::FontSize(SpecifiedValue::Specified(
+            FontSize::parse_str("10px").unwrap(),
+        )));
+
+        let mut values = ComputedValues::default();
+        specified.to_computed_values(&mut values);
+
+        let params = ViewParams::new(Dpi::new(96.0, 96.0), 100.0, 100.0);
+
+        let larger = FontSize::parse_str("larger").unwrap().compute(&values);
+        assert_approx_eq_cairo!(larger.normalize(&values, &params), 12.0);
+
+        let smaller = FontSize::parse_str("smaller").unwrap().compute(&values);
+        assert_approx_eq_cairo!(smaller.normalize(&values, &params), 10.0 / 1.2);
+    }
+
     #[test]
     fn parses_font_weight() {
         assert_eq!(
@@ -7,9 +7,9 @@ use crate::bbox::BoundingBox;
 use crate::css::{Origin, Stylesheet};
 use crate::document::{AcquiredNodes, Document};
 use crate::dpi::Dpi;
-use crate::drawing_ctx::DrawingCtx;
+use crate::drawing_ctx::{DrawingCtx, RenderingOptions};
 use crate::element::Element;
-use crate::error::{DefsLookupErrorKind, LoadingError, RenderingError};
+use crate::error::{DefsLookupErrorKind, LoadingError, ProcessingError, RenderingError};
 use crate::node::{CascadedValues, Node, NodeBorrow};
 use crate::rect::Rect;
 use crate::structure::IntrinsicDimensions;
@@ -26,6 +26,9 @@ pub struct LoadOptions {
 
     /// Whether to keep original (undecoded) image data to embed in Cairo PDF surfaces.
     pub keep_image_data: bool,
+
+    /// Whether unknown elements in the SVG namespace are a loading error.
+    pub strict_elements: bool,
 }
 
 impl LoadOptions {
@@ -35,6 +38,7 @@ impl LoadOptions {
             base_url,
             unlimited_size: false,
             keep_image_data: false,
+            strict_elements: false,
         }
     }
 
@@ -56,6 +60,15 @@ impl LoadOptions {
         self
     }
 
+    /// Sets whether unknown elements in the SVG namespace should make loading fail.
+    ///
+    /// Normally they are ignored, along with their children.  Failing instead is useful for
+    /// validation tools, to catch typos in element names.
+    pub fn with_strict_elements(mut self, strict: bool) -> Self {
+        self.strict_elements = strict;
+        self
+    }
+
     /// Creates a new `LoadOptions` with a different `base_url`.
     ///
     /// This is used when loading a referenced file that may in turn cause other files
@@ -65,6 +78,7 @@ impl LoadOptions {
             base_url: Some((**base_url).clone()),
             unlimited_size: self.unlimited_size,
             keep_image_data: self.keep_image_data,
+            strict_elements: self.strict_elements,
         }
     }
 }
@@ -117,7 +131,11 @@ impl Handle {
 
         let target = cairo::ImageSurface::create(cairo::Format::Rgb24, 1, 1)?;
         let cr = cairo::Context::new(&target);
-        let mut draw_ctx = DrawingCtx::new(Some(node), &cr, viewport, dpi, true, is_testing);
+        let options = RenderingOptions {
+            testing: is_testing,
+            ..Default::default()
+        };
+        let mut draw_ctx = DrawingCtx::new(Some(node), &cr, viewport, dpi, true, options);
 
         let bbox = draw_ctx.draw_node_from_stack(
             &root,
@@ -224,9 +242,9 @@ impl Handle {
         cr: &cairo::Context,
         viewport: &cairo::Rectangle,
         dpi: Dpi,
-        is_testing: bool,
+        options: RenderingOptions,
     ) -> Result<(), RenderingError> {
-        self.render_layer(cr, None, viewport, dpi, is_testing)
+        self.render_layer(cr, None, viewport, dpi, options)
     }
 
     pub fn render_layer(
@@ -235,7 +253,7 @@ impl Handle {
         id: Option<&str>,
         viewport: &cairo::Rectangle,
         dpi: Dpi,
-        is_testing: bool,
+        options: RenderingOptions,
     ) -> Result<(), RenderingError> {
         check_cairo_context(cr)?;
 
@@ -254,7 +272,7 @@ impl Handle {
             Rect::from(*viewport),
             dpi,
             false,
-            is_testing,
+            options,
         );
 
         let res = draw_ctx
@@ -279,7 +297,11 @@ impl Handle {
     ) -> Result<BoundingBox, RenderingError> {
         let target = cairo::ImageSurface::create(cairo::Format::Rgb24, 1, 1)?;
         let cr = cairo::Context::new(&target);
-        let mut draw_ctx = DrawingCtx::new(None, &cr, unit_rectangle(), dpi, true, is_testing);
+        let options = RenderingOptions {
+            testing: is_testing,
+            ..Default::default()
+        };
+        let mut draw_ctx = DrawingCtx::new(None, &cr, unit_rectangle(), dpi, true, options);
 
         draw_ctx.draw_node_from_stack(
             node,
@@ -318,13 +340,13 @@ impl Handle {
         id: Option<&str>,
         element_viewport: &cairo::Rectangle,
         dpi: Dpi,
-        is_testing: bool,
+        options: RenderingOptions,
     ) -> Result<(), RenderingError> {
         check_cairo_context(cr)?;
 
         let node = self.get_node_or_root(id)?;
 
-        let bbox = self.get_bbox_for_element(&node, dpi, is_testing)?;
+        let bbox = self.get_bbox_for_element(&node, dpi, options.testing)?;
 
         if bbox.ink_rect.is_none() || bbox.rect.is_none() {
             // Nothing to draw
@@ -348,7 +370,7 @@ impl Handle {
         cr.scale(factor, factor);
         cr.translate(-ink_r.x0, -ink_r.y0);
 
-        let mut draw_ctx = DrawingCtx::new(None, &cr, unit_rectangle(), dpi, false, is_testing);
+        let mut draw_ctx = DrawingCtx::new(None, &cr, unit_rectangle(), dpi, false, options);
 
         let res = draw_ctx
             .draw_node_from_stack(
@@ -364,6 +386,10 @@ impl Handle {
         res
     }
 
+    pub fn get_diagnostics(&self) -> &[ProcessingError] {
+        self.document.diagnostics()
+    }
+
     pub fn get_intrinsic_dimensions(&self) -> IntrinsicDimensions {
         borrow_element_as!(self.document.root(), Svg).get_intrinsic_dimensions()
     }
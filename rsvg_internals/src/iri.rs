@@ -15,7 +15,9 @@ use crate::parsers::Parse;
 #[derive(Debug, Clone, PartialEq)]
 pub enum IRI {
     None,
-    Resource(Fragment),
+
+    /// A reference to an element, and the URL as it was written in the document.
+    Resource(Fragment, String),
 }
 
 impl Default for IRI {
@@ -29,7 +31,17 @@ impl IRI {
     pub fn get(&self) -> Option<&Fragment> {
         match *self {
             IRI::None => None,
-            IRI::Resource(ref f) => Some(f),
+            IRI::Resource(ref f, _) => Some(f),
+        }
+    }
+
+    /// Returns the URL of an `IRI::Resource` as the author wrote it, or `None`.
+    ///
+    /// This is useful for error messages when the reference cannot be resolved.
+    pub fn source(&self) -> Option<&str> {
+        match *self {
+            IRI::None => None,
+            IRI::Resource(_, ref source) => Some(source),
         }
     }
 }
@@ -46,6 +58,14 @@ impl Parse for IRI {
 
             let url = parser.expect_url()?;
 
+            // A fragment can never resolve to a node if it is empty, so reject it here
+            // with a more specific message than the generic url parse error.
+            if url.ends_with('#') {
+                return Err(
+                    loc.new_custom_error(ValueErrorKind::parse_error("empty fragment identifier"))
+                );
+            }
+
             let href =
                 Href::parse(&url).map_err(|e| loc.new_custom_error(ValueErrorKind::from(e)))?;
 
@@ -53,7 +73,7 @@ impl Parse for IRI {
                 Href::PlainUrl(_) => Err(loc.new_custom_error(ValueErrorKind::parse_error(
                     "href requires a fragment identifier",
                 ))),
-                Href::WithFragment(f) => Ok(IRI::Resource(f)),
+                Href::WithFragment(f) => Ok(IRI::Resource(f, url.to_string())),
             }
         }
     }
@@ -72,32 +92,69 @@ mod tests {
     fn parses_url() {
         assert_eq!(
             IRI::parse_str("url(#bar)"),
-            Ok(IRI::Resource(Fragment::new(None, "bar".to_string())))
+            Ok(IRI::Resource(
+                Fragment::new(None, "bar".to_string()),
+                "#bar".to_string()
+            ))
         );
 
         assert_eq!(
             IRI::parse_str("url(foo#bar)"),
-            Ok(IRI::Resource(Fragment::new(
-                Some("foo".to_string()),
-                "bar".to_string()
-            )))
+            Ok(IRI::Resource(
+                Fragment::new(Some("foo".to_string()), "bar".to_string()),
+                "foo#bar".to_string()
+            ))
         );
 
         // be permissive if the closing ) is missing
         assert_eq!(
             IRI::parse_str("url(#bar"),
-            Ok(IRI::Resource(Fragment::new(None, "bar".to_string())))
+            Ok(IRI::Resource(
+                Fragment::new(None, "bar".to_string()),
+                "#bar".to_string()
+            ))
         );
         assert_eq!(
             IRI::parse_str("url(foo#bar"),
-            Ok(IRI::Resource(Fragment::new(
-                Some("foo".to_string()),
-                "bar".to_string()
-            )))
+            Ok(IRI::Resource(
+                Fragment::new(Some("foo".to_string()), "bar".to_string()),
+                "foo#bar".to_string()
+            ))
         );
 
         assert!(IRI::parse_str("").is_err());
         assert!(IRI::parse_str("foo").is_err());
         assert!(IRI::parse_str("url(foo)bar").is_err());
     }
+
+    #[test]
+    fn retains_source_url() {
+        assert_eq!(
+            IRI::parse_str("url(foo#bar)").unwrap().source(),
+            Some("foo#bar")
+        );
+        assert_eq!(
+            IRI::parse_str("url( \"foo#bar\" )").unwrap().source(),
+            Some("foo#bar")
+        );
+        assert_eq!(IRI::None.source(), None);
+    }
+
+    #[test]
+    fn empty_fragment_yields_error() {
+        assert!(IRI::parse_str("url(#)").is_err());
+        assert!(IRI::parse_str("url(foo#)").is_err());
+        assert!(IRI::parse_str("url(#bar)").is_ok());
+    }
+
+    #[test]
+    fn parses_url_with_query() {
+        assert_eq!(
+            IRI::parse_str("url(foo.svg?v=2#bar)"),
+            Ok(IRI::Resource(
+                Fragment::new(Some("foo.svg?v=2".to_string()), "bar".to_string()),
+                "foo.svg?v=2#bar".to_string()
+            ))
+        );
+    }
 }
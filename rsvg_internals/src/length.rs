@@ -49,6 +49,7 @@ use crate::drawing_ctx::ViewParams;
 use crate::error::*;
 use crate::parsers::{finite_f32, Parse};
 use crate::properties::ComputedValues;
+use crate::util::clamp;
 
 /// Units for length values.
 // This needs to be kept in sync with `rsvg.h:RsvgUnit`.
@@ -81,6 +82,12 @@ pub enum LengthUnit {
 
     /// Picas (12 points)
     Pc,
+
+    /// Advance width of the "0" glyph in the current font
+    ///
+    /// We don't query the font for this; it is approximated as half the font size,
+    /// the same as `Ex`.
+    Ch,
 }
 
 /// A CSS length value.
@@ -113,6 +120,50 @@ impl RsvgLength {
     pub fn new(l: f64, unit: LengthUnit) -> RsvgLength {
         RsvgLength { length: l, unit }
     }
+
+    /// Creates a length in pixels.
+    pub fn px(l: f64) -> RsvgLength {
+        RsvgLength::new(l, LengthUnit::Px)
+    }
+
+    /// Creates a percentage length.
+    ///
+    /// As with [`LengthUnit::Percent`], the `fraction` is 1.0 for 100%.
+    ///
+    /// [`LengthUnit::Percent`]: enum.LengthUnit.html#variant.Percent
+    pub fn percent(fraction: f64) -> RsvgLength {
+        RsvgLength::new(fraction, LengthUnit::Percent)
+    }
+
+    /// Creates a length in units of the font size.
+    pub fn em(l: f64) -> RsvgLength {
+        RsvgLength::new(l, LengthUnit::Em)
+    }
+
+    /// Returns this length multiplied by `factor`, keeping its unit.
+    pub fn scaled(&self, factor: f64) -> RsvgLength {
+        RsvgLength::new(self.length * factor, self.unit)
+    }
+
+    /// Returns whether the length's used value depends on the size of the viewport.
+    ///
+    /// Lengths for which this returns `false` do not need to be normalized again when
+    /// the viewport is resized.
+    pub fn is_viewport_relative(&self) -> bool {
+        match self.unit {
+            LengthUnit::Percent => true,
+
+            LengthUnit::Px
+            | LengthUnit::Em
+            | LengthUnit::Ex
+            | LengthUnit::In
+            | LengthUnit::Cm
+            | LengthUnit::Mm
+            | LengthUnit::Pt
+            | LengthUnit::Pc
+            | LengthUnit::Ch => false,
+        }
+    }
 }
 
 /// Used for the type parameter of `Length<N: Normalize>`.
@@ -215,6 +266,12 @@ impl<N: Normalize> From<Length<N>> for RsvgLength {
     }
 }
 
+impl Parse for RsvgLength {
+    fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<RsvgLength, ParseError<'i>> {
+        Length::<Both>::parse(parser).map(RsvgLength::from)
+    }
+}
+
 impl<N: Normalize> Default for Length<N> {
     fn default() -> Self {
         Length::new(0.0, LengthUnit::Px)
@@ -226,6 +283,12 @@ const CM_PER_INCH: f64 = 2.54;
 const MM_PER_INCH: f64 = 25.4;
 const PICA_PER_INCH: f64 = 6.0;
 
+/// Parses a length.
+///
+/// Note that cssparser hands us numbers as `f32`, so lengths only have single-precision
+/// accuracy even though we store them as `f64`.  Exponents are accepted (`1e2px`), but
+/// a value that overflows `f32` becomes infinite and is rejected with a parse error
+/// instead of yielding an infinite length.
 impl<N: Normalize> Parse for Length<N> {
     fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Length<N>, ParseError<'i>> {
         let length = {
@@ -248,20 +311,19 @@ impl<N: Normalize> Parse for Length<N> {
                     let value =
                         f64::from(finite_f32(value).map_err(|e| parser.new_custom_error(e))?);
 
-                    match unit.as_ref() {
-                        "px" => Length::new(value, LengthUnit::Px),
-                        "em" => Length::new(value, LengthUnit::Em),
-                        "ex" => Length::new(value, LengthUnit::Ex),
-                        "in" => Length::new(value, LengthUnit::In),
-                        "cm" => Length::new(value, LengthUnit::Cm),
-                        "mm" => Length::new(value, LengthUnit::Mm),
-                        "pt" => Length::new(value, LengthUnit::Pt),
-                        "pc" => Length::new(value, LengthUnit::Pc),
-
-                        _ => return Err(parser.new_unexpected_token_error(token.clone())),
+                    match unit_from_str(unit) {
+                        Some(unit) => Length::new(value, unit),
+                        None => return Err(parser.new_unexpected_token_error(token.clone())),
                     }
                 }
 
+                // Something like width="px", which is a common mistake
+                Token::Ident(ref ident) if unit_from_str(ident).is_some() => {
+                    return Err(parser.new_custom_error(ValueErrorKind::parse_error(
+                        "found a unit without a number",
+                    )));
+                }
+
                 _ => return Err(parser.new_unexpected_token_error(token.clone())),
             }
         };
@@ -270,6 +332,21 @@ impl<N: Normalize> Parse for Length<N> {
     }
 }
 
+fn unit_from_str(unit: &str) -> Option<LengthUnit> {
+    match unit {
+        "px" => Some(LengthUnit::Px),
+        "em" => Some(LengthUnit::Em),
+        "ex" => Some(LengthUnit::Ex),
+        "ch" => Some(LengthUnit::Ch),
+        "in" => Some(LengthUnit::In),
+        "cm" => Some(LengthUnit::Cm),
+        "mm" => Some(LengthUnit::Mm),
+        "pt" => Some(LengthUnit::Pt),
+        "pc" => Some(LengthUnit::Pc),
+        _ => None,
+    }
+}
+
 impl<N: Normalize> Length<N> {
     /// Creates a Length.
     ///
@@ -317,39 +394,107 @@ impl<N: Normalize> Length<N> {
     /// based on the current element's set of `ComputedValues` (e.g. for lengths with `Em`
     /// units that need to be resolved against the current font size).
     pub fn normalize(&self, values: &ComputedValues, params: &ViewParams) -> f64 {
+        self.resolve(
+            (params.view_box_width, params.view_box_height),
+            (params.dpi.x, params.dpi.y),
+            font_size_from_values(values, params),
+        )
+    }
+
+    /// Normalizes a slice of lengths into used values, like calling [`normalize`] on each.
+    ///
+    /// The font size is computed only once for the whole slice.
+    ///
+    /// [`normalize`]: #method.normalize
+    pub fn normalize_all(
+        lengths: &[Length<N>],
+        values: &ComputedValues,
+        params: &ViewParams,
+    ) -> Vec<f64> {
+        let viewport = (params.view_box_width, params.view_box_height);
+        let dpi = (params.dpi.x, params.dpi.y);
+        let font_size = font_size_from_values(values, params);
+
+        lengths
+            .iter()
+            .map(|l| l.resolve(viewport, dpi, font_size))
+            .collect()
+    }
+
+    /// Resolves a length to pixels, given the viewport size, the DPI, and the font size.
+    ///
+    /// This is like [`normalize`], but the `viewport` and `dpi` are given as explicit
+    /// `(horizontal, vertical)` pairs, and the `font_size` in pixels, so it can be used
+    /// without a drawing context.  As with `normalize`, the length's orientation decides
+    /// which components are used.
+    ///
+    /// [`normalize`]: #method.normalize
+    pub fn resolve(&self, viewport: (f64, f64), dpi: (f64, f64), font_size: f64) -> f64 {
+        let (view_width, view_height) = viewport;
+        let (dpi_x, dpi_y) = dpi;
+
         match self.unit {
             LengthUnit::Px => self.length,
 
             LengthUnit::Percent => {
-                self.length
-                    * <N as Normalize>::normalize(params.view_box_width, params.view_box_height)
+                self.length * <N as Normalize>::normalize(view_width, view_height)
             }
 
-            LengthUnit::Em => self.length * font_size_from_values(values, params),
+            LengthUnit::Em => self.length * font_size,
 
-            LengthUnit::Ex => self.length * font_size_from_values(values, params) / 2.0,
+            LengthUnit::Ex => self.length * font_size / 2.0,
 
-            LengthUnit::In => self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y),
+            // FIXME: this should be the advance of the "0" glyph in the current font.
+            LengthUnit::Ch => self.length * font_size / 2.0,
 
-            LengthUnit::Cm => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / CM_PER_INCH
-            }
+            LengthUnit::In => self.length * <N as Normalize>::normalize(dpi_x, dpi_y),
 
-            LengthUnit::Mm => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y) / MM_PER_INCH
-            }
+            LengthUnit::Cm => self.length * <N as Normalize>::normalize(dpi_x, dpi_y) / CM_PER_INCH,
+
+            LengthUnit::Mm => self.length * <N as Normalize>::normalize(dpi_x, dpi_y) / MM_PER_INCH,
 
             LengthUnit::Pt => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y)
-                    / POINTS_PER_INCH
+                self.length * <N as Normalize>::normalize(dpi_x, dpi_y) / POINTS_PER_INCH
             }
 
             LengthUnit::Pc => {
-                self.length * <N as Normalize>::normalize(params.dpi.x, params.dpi.y)
-                    / PICA_PER_INCH
+                self.length * <N as Normalize>::normalize(dpi_x, dpi_y) / PICA_PER_INCH
             }
         }
     }
+
+    /// Normalizes a specified length into a used value, clamped to the `[min, max]` range.
+    pub fn normalize_clamped(
+        &self,
+        values: &ComputedValues,
+        params: &ViewParams,
+        min: f64,
+        max: f64,
+    ) -> f64 {
+        clamp(self.normalize(values, params), min, max)
+    }
+
+    /// Normalizes a specified length like [`normalize`], but rounds lengths in physical
+    /// units (`in`, `cm`, `mm`, `pt`, `pc`) to the nearest whole device pixel.
+    ///
+    /// Other units, like `px`, percentages, or font-relative units, are not rounded.
+    ///
+    /// [`normalize`]: #method.normalize
+    pub fn normalize_snapped(&self, values: &ComputedValues, params: &ViewParams) -> f64 {
+        let length = self.normalize(values, params);
+
+        match self.unit {
+            LengthUnit::In | LengthUnit::Cm | LengthUnit::Mm | LengthUnit::Pt | LengthUnit::Pc => {
+                length.round()
+            }
+
+            LengthUnit::Percent
+            | LengthUnit::Px
+            | LengthUnit::Em
+            | LengthUnit::Ex
+            | LengthUnit::Ch => length,
+        }
+    }
 }
 
 fn font_size_from_values(values: &ComputedValues, params: &ViewParams) -> f64 {
@@ -364,6 +509,7 @@ fn font_size_from_values(values: &ComputedValues, params: &ViewParams) -> f64 {
         // matches the default from the FontSize property.
         LengthUnit::Em => v.length * 12.0,
         LengthUnit::Ex => v.length * 12.0 / 2.0,
+        LengthUnit::Ch => v.length * 12.0 / 2.0,
 
         // FontSize always is a Both, per properties.rs
         LengthUnit::In => v.length * Both::normalize(params.dpi.x, params.dpi.y),
@@ -386,6 +532,9 @@ fn viewport_percentage(x: f64, y: f64) -> f64 {
 mod tests {
     use super::*;
 
+    use cssparser::{ParseErrorKind, ParserInput};
+    use matches::matches;
+
     use crate::dpi::Dpi;
     use crate::float_eq_cairo::ApproxEqCairo;
 
@@ -426,6 +575,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_font_ch() {
+        assert_eq!(
+            Length::<Horizontal>::parse_str("3ch"),
+            Ok(Length::<Horizontal>::new(3.0, LengthUnit::Ch))
+        );
+    }
+
     #[test]
     fn parses_physical_units() {
         assert_eq!(
@@ -454,16 +611,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_rsvg_length_from_shared_parser() {
+        let mut input = ParserInput::new("10px 50%, 3em");
+        let mut parser = Parser::new(&mut input);
+
+        assert_eq!(
+            RsvgLength::parse(&mut parser),
+            Ok(RsvgLength::new(10.0, LengthUnit::Px))
+        );
+        assert_eq!(
+            RsvgLength::parse(&mut parser),
+            Ok(RsvgLength::new(0.5, LengthUnit::Percent))
+        );
+        assert!(parser.expect_comma().is_ok());
+        assert_eq!(
+            RsvgLength::parse(&mut parser),
+            Ok(RsvgLength::new(3.0, LengthUnit::Em))
+        );
+        assert!(parser.is_exhausted());
+    }
+
+    #[test]
+    fn rsvg_length_constructors_match_parsed_lengths() {
+        assert_eq!(RsvgLength::parse_str("10px"), Ok(RsvgLength::px(10.0)));
+        assert_eq!(RsvgLength::parse_str("25%"), Ok(RsvgLength::percent(0.25)));
+        assert_eq!(RsvgLength::parse_str("3em"), Ok(RsvgLength::em(3.0)));
+    }
+
+    #[test]
+    fn scales_length_keeping_unit() {
+        assert_eq!(
+            RsvgLength::parse_str("10px").unwrap().scaled(2.0),
+            RsvgLength::parse_str("20px").unwrap()
+        );
+        assert_eq!(
+            RsvgLength::parse_str("3em").unwrap().scaled(0.5),
+            RsvgLength::em(1.5)
+        );
+        assert_eq!(
+            RsvgLength::parse_str("25%").unwrap().scaled(2.0),
+            RsvgLength::percent(0.5)
+        );
+    }
+
+    #[test]
+    fn detects_viewport_relative_lengths() {
+        assert!(RsvgLength::percent(0.5).is_viewport_relative());
+
+        assert!(!RsvgLength::px(10.0).is_viewport_relative());
+        assert!(!RsvgLength::em(1.0).is_viewport_relative());
+        assert!(!RsvgLength::new(1.0, LengthUnit::In).is_viewport_relative());
+    }
+
+    #[test]
+    fn parses_exponents() {
+        assert_eq!(
+            RsvgLength::parse_str("1e2px"),
+            Ok(RsvgLength::new(100.0, LengthUnit::Px))
+        );
+
+        let length = RsvgLength::parse_str("1e10px").unwrap();
+        assert!(length.length.is_finite());
+        assert_approx_eq_cairo!(length.length, 1e10_f64);
+        assert_eq!(length.unit, LengthUnit::Px);
+    }
+
+    #[test]
+    fn f32_overflow_yields_error() {
+        assert!(RsvgLength::parse_str("1e39px").is_err());
+        assert!(RsvgLength::parse_str("-1e39").is_err());
+        assert!(RsvgLength::parse_str("1e39%").is_err());
+    }
+
     #[test]
     fn empty_length_yields_error() {
         assert!(Length::<Both>::parse_str("").is_err());
     }
 
+    #[test]
+    fn unit_without_number_yields_specific_error() {
+        for s in &["px", "em", "mm"] {
+            assert_eq!(
+                RsvgLength::parse_str(s).map_err(|e| e.kind),
+                Err(ParseErrorKind::Custom(ValueErrorKind::parse_error(
+                    "found a unit without a number"
+                )))
+            );
+        }
+
+        // Other identifiers are still just unexpected tokens
+        assert!(matches!(
+            RsvgLength::parse_str("foo").map_err(|e| e.kind),
+            Err(ParseErrorKind::Basic(_))
+        ));
+    }
+
     #[test]
     fn invalid_unit_yields_error() {
         assert!(Length::<Both>::parse_str("8furlong").is_err());
     }
 
+    #[test]
+    fn font_size_keywords_yield_error() {
+        // These are only valid for font-size; see FontSize.
+        assert!(Length::<Both>::parse_str("larger").is_err());
+        assert!(Length::<Both>::parse_str("xx-small").is_err());
+    }
+
     #[test]
     fn check_nonnegative_works() {
         // and_then with anonymous function
@@ -491,6 +746,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalize_all_matches_individual_normalize() {
+        let params = ViewParams::new(Dpi::new(40.0, 50.0), 100.0, 200.0);
+
+        let values = ComputedValues::default();
+
+        let lengths = [
+            Length::<Both>::new(10.0, LengthUnit::Px),
+            Length::<Both>::new(0.5, LengthUnit::Percent),
+            Length::<Both>::new(2.0, LengthUnit::Em),
+            Length::<Both>::new(1.0, LengthUnit::In),
+            Length::<Both>::new(3.0, LengthUnit::Mm),
+        ];
+
+        let normalized = Length::normalize_all(&lengths, &values, &params);
+
+        assert_eq!(normalized.len(), lengths.len());
+
+        for (l, n) in lengths.iter().zip(normalized.iter()) {
+            assert_approx_eq_cairo!(*n, l.normalize(&values, &params));
+        }
+
+        assert!(Length::<Both>::normalize_all(&[], &values, &params).is_empty());
+    }
+
+    #[test]
+    fn normalize_clamped_works() {
+        let params = ViewParams::new(Dpi::new(40.0, 40.0), 100.0, 100.0);
+
+        let values = ComputedValues::default();
+
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(-5.0, LengthUnit::Px)
+                .normalize_clamped(&values, &params, 0.0, 10.0),
+            0.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(5.0, LengthUnit::Px).normalize_clamped(&values, &params, 0.0, 10.0),
+            5.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(0.5, LengthUnit::Percent)
+                .normalize_clamped(&values, &params, 0.0, 10.0),
+            10.0
+        );
+    }
+
     #[test]
     fn normalize_absolute_units_works() {
         let params = ViewParams::new(Dpi::new(40.0, 50.0), 100.0, 100.0);
@@ -524,6 +828,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalize_snapped_rounds_physical_units_only() {
+        let params = ViewParams::new(Dpi::new(96.0, 96.0), 100.0, 100.0);
+
+        let values = ComputedValues::default();
+
+        let length = Length::<Horizontal>::new(0.3, LengthUnit::In);
+        assert_approx_eq_cairo!(length.normalize(&values, &params), 28.8);
+        assert_approx_eq_cairo!(length.normalize_snapped(&values, &params), 29.0);
+
+        let length = Length::<Horizontal>::new(0.2, LengthUnit::In);
+        assert_approx_eq_cairo!(length.normalize_snapped(&values, &params), 19.0);
+
+        let length = Length::<Horizontal>::new(0.2885, LengthUnit::Percent);
+        assert_approx_eq_cairo!(length.normalize_snapped(&values, &params), 28.85);
+
+        let length = Length::<Horizontal>::new(0.3, LengthUnit::Em);
+        assert_approx_eq_cairo!(length.normalize_snapped(&values, &params), 3.6);
+    }
+
     #[test]
     fn normalize_percent_works() {
         let params = ViewParams::new(Dpi::new(40.0, 40.0), 100.0, 200.0);
@@ -559,4 +883,59 @@ mod tests {
             6.0
         );
     }
+
+    #[test]
+    fn resolve_uses_orientation_of_viewport_and_dpi() {
+        let viewport = (300.0, 400.0);
+        let dpi = (96.0, 72.0);
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(0.5, LengthUnit::Percent).resolve(viewport, dpi, 12.0),
+            150.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::new(0.5, LengthUnit::Percent).resolve(viewport, dpi, 12.0),
+            200.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Both>::new(0.1, LengthUnit::Percent).resolve(viewport, dpi, 12.0),
+            50.0 / 2.0f64.sqrt()
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(2.0, LengthUnit::In).resolve(viewport, dpi, 12.0),
+            192.0
+        );
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::new(2.0, LengthUnit::In).resolve(viewport, dpi, 12.0),
+            144.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Vertical>::new(2.0, LengthUnit::Em).resolve(viewport, dpi, 10.0),
+            20.0
+        );
+    }
+
+    #[test]
+    fn normalize_font_ch_works() {
+        let params = ViewParams::new(Dpi::new(40.0, 40.0), 100.0, 200.0);
+
+        let values = ComputedValues::default();
+
+        // Half the default font size of 12
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(2.0, LengthUnit::Ch).normalize(&values, &params),
+            12.0
+        );
+
+        assert_approx_eq_cairo!(
+            Length::<Horizontal>::new(2.0, LengthUnit::Ch).resolve(
+                (100.0, 200.0),
+                (40.0, 40.0),
+                20.0
+            ),
+            20.0
+        );
+    }
 }
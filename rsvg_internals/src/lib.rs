@@ -62,7 +62,11 @@ pub use crate::color::Color;
 
 pub use crate::dpi::Dpi;
 
-pub use crate::error::{DefsLookupErrorKind, HrefError, LoadingError, RenderingError};
+pub use crate::drawing_ctx::RenderingOptions;
+
+pub use crate::error::{
+    DefsLookupErrorKind, HrefError, LoadingError, ProcessingError, RenderingError,
+};
 
 pub use crate::handle::{Handle, LoadOptions};
 
@@ -645,7 +645,7 @@ pub fn render_markers_for_path(
         path,
         draw_ctx.empty_bbox(),
         &mut |marker_type: MarkerType, x: f64, y: f64, computed_angle: Angle| {
-            if let IRI::Resource(ref marker) = match marker_type {
+            if let IRI::Resource(ref marker, _) = match marker_type {
                 MarkerType::Start => &marker_start,
                 MarkerType::Middle => &marker_mid,
                 MarkerType::End => &marker_end,
@@ -248,6 +248,38 @@ macro_rules! borrow_element_as {
     };
 }
 
+/// Helper trait for finding children of a node
+pub trait NodeChildren {
+    /// Returns the only element child for which `predicate` returns `true`.
+    ///
+    /// Returns an error if no children match, or if more than one does.  This is for
+    /// elements like `<feDiffuseLighting>`, which must have exactly one light source child:
+    ///
+    /// ```ignore
+    /// let light = node.exactly_one_child(|e| matches!(*e, Element::FePointLight(_)))?;
+    /// ```
+    fn exactly_one_child<P>(&self, predicate: P) -> Result<Node, ChildCountError>
+    where
+        P: Fn(&Element) -> bool;
+}
+
+impl NodeChildren for Node {
+    fn exactly_one_child<P>(&self, predicate: P) -> Result<Node, ChildCountError>
+    where
+        P: Fn(&Element) -> bool,
+    {
+        let mut matching = self
+            .children()
+            .filter(|c| c.is_element() && predicate(&c.borrow_element()));
+
+        match (matching.next(), matching.next()) {
+            (Some(child), None) => Ok(child),
+            (None, _) => Err(ChildCountError::NoChildren),
+            (Some(_), Some(_)) => Err(ChildCountError::MultipleChildren),
+        }
+    }
+}
+
 /// Helper trait for cascading recursively
 pub trait NodeCascade {
     fn cascade(&mut self, values: &ComputedValues);
@@ -325,3 +357,57 @@ impl NodeDraw for Node {
         Ok(bbox)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matches::matches;
+
+    use crate::document::load_document;
+
+    fn is_light_source(e: &Element) -> bool {
+        matches!(
+            *e,
+            Element::FeDistantLight(_) | Element::FePointLight(_) | Element::FeSpotLight(_)
+        )
+    }
+
+    #[test]
+    fn exactly_one_child_works() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <filter>
+    <feDiffuseLighting id="none">
+      <feFlood/>
+    </feDiffuseLighting>
+    <feDiffuseLighting id="one">
+      <feFlood/>
+      <fePointLight id="light"/>
+    </feDiffuseLighting>
+    <feDiffuseLighting id="many">
+      <feDistantLight/>
+      <feSpotLight/>
+    </feDiffuseLighting>
+  </filter>
+</svg>
+"#,
+        );
+
+        let none = document.lookup_node_by_id("none").unwrap();
+        assert!(matches!(
+            none.exactly_one_child(is_light_source),
+            Err(ChildCountError::NoChildren)
+        ));
+
+        let one = document.lookup_node_by_id("one").unwrap();
+        let light = document.lookup_node_by_id("light").unwrap();
+        assert!(one.exactly_one_child(is_light_source).unwrap() == light);
+
+        let many = document.lookup_node_by_id("many").unwrap();
+        assert!(matches!(
+            many.exactly_one_child(is_light_source),
+            Err(ChildCountError::MultipleChildren)
+        ));
+    }
+}
@@ -92,6 +92,11 @@ mod tests {
             NumberList::parse_str("1, 2, 3.0, 4, 5", NumberListLength::Unbounded),
             Ok(NumberList(vec![1.0, 2.0, 3.0, 4.0, 5.0]))
         );
+
+        assert_eq!(
+            NumberList::parse_str("1 2,3  4", NumberListLength::Unbounded),
+            Ok(NumberList(vec![1.0, 2.0, 3.0, 4.0]))
+        );
     }
 
     #[test]
@@ -105,6 +110,7 @@ mod tests {
         assert!(NumberList::parse_str("1 foo", NumberListLength::Exact(2)).is_err());
         assert!(NumberList::parse_str("1 foo 2", NumberListLength::Exact(2)).is_err());
         assert!(NumberList::parse_str("1,foo", NumberListLength::Exact(2)).is_err());
+        assert!(NumberList::parse_str("1 x 3", NumberListLength::Unbounded).is_err());
 
         // too many
         assert!(NumberList::parse_str("1 2", NumberListLength::Exact(1)).is_err());
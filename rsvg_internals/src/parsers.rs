@@ -150,6 +150,12 @@ macro_rules! parse_identifiers {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CustomIdent(String);
 
+impl CustomIdent {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 impl Parse for CustomIdent {
     fn parse<'i>(parser: &mut Parser<'i, '_>) -> Result<Self, ParseError<'i>> {
         let loc = parser.current_source_location();
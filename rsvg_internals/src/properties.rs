@@ -812,6 +812,8 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use matches::matches;
+
     use crate::iri::IRI;
     use crate::length::*;
 
@@ -937,4 +939,75 @@ mod tests {
 
         assert_eq!(computed.opacity(), half_opacity.clone());
     }
+
+    #[test]
+    fn parses_inherit_for_length_properties() {
+        let parse = |s| {
+            let mut input = ParserInput::new(s);
+            let mut parser = Parser::new(&mut input);
+            parse_property(
+                &QualName::new(None, ns!(), local_name!("stroke-width")),
+                &mut parser,
+                true,
+            )
+        };
+
+        assert!(matches!(
+            parse("inherit"),
+            Ok(ParsedProperty::StrokeWidth(SpecifiedValue::Inherit))
+        ));
+
+        match parse("2.5mm") {
+            Ok(ParsedProperty::StrokeWidth(SpecifiedValue::Specified(StrokeWidth(length)))) => {
+                assert_eq!(length, Length::<Both>::new(2.5, LengthUnit::Mm));
+            }
+            _ => panic!("expected a stroke width"),
+        }
+
+        assert!(parse("foo").is_err());
+    }
+
+    #[test]
+    fn parses_stroke_dasharray_property() {
+        use crate::dasharray::Dasharray;
+
+        let parse = |s| {
+            let mut input = ParserInput::new(s);
+            let mut parser = Parser::new(&mut input);
+            parse_property(
+                &QualName::new(None, ns!(), local_name!("stroke-dasharray")),
+                &mut parser,
+                true,
+            )
+        };
+
+        assert!(matches!(
+            parse("none"),
+            Ok(ParsedProperty::StrokeDasharray(SpecifiedValue::Specified(
+                StrokeDasharray(Dasharray::None)
+            )))
+        ));
+
+        assert!(matches!(
+            parse("inherit"),
+            Ok(ParsedProperty::StrokeDasharray(SpecifiedValue::Inherit))
+        ));
+
+        match parse("5, 10") {
+            Ok(ParsedProperty::StrokeDasharray(SpecifiedValue::Specified(StrokeDasharray(
+                Dasharray::Array(dashes),
+            )))) => {
+                assert_eq!(
+                    dashes,
+                    vec![
+                        Length::<Both>::new(5.0, LengthUnit::Px),
+                        Length::<Both>::new(10.0, LengthUnit::Px),
+                    ]
+                );
+            }
+            _ => panic!("expected a dash array"),
+        }
+
+        assert!(parse("").is_err());
+    }
 }
@@ -165,6 +165,29 @@ make_property!(
     "sRGB" => Srgb,
 );
 
+#[cfg(test)]
+#[test]
+fn parses_color_interpolation_filters() {
+    assert_eq!(
+        ColorInterpolationFilters::parse_str("auto").unwrap(),
+        ColorInterpolationFilters::Auto
+    );
+    assert_eq!(
+        ColorInterpolationFilters::parse_str("sRGB").unwrap(),
+        ColorInterpolationFilters::Srgb
+    );
+    assert_eq!(
+        ColorInterpolationFilters::parse_str("linearRGB").unwrap(),
+        ColorInterpolationFilters::LinearRgb
+    );
+    assert!(ColorInterpolationFilters::parse_str("rgb").is_err());
+
+    assert_eq!(
+        ColorInterpolationFilters::default(),
+        ColorInterpolationFilters::LinearRgb
+    );
+}
+
 // https://www.w3.org/TR/SVG/text.html#DirectionProperty
 make_property!(
     ComputedValues,
@@ -264,6 +287,20 @@ make_property!(
     newtype_parse: cssparser::Color,
 );
 
+#[cfg(test)]
+#[test]
+fn parses_flood_color_with_modern_syntax() {
+    assert_eq!(
+        FloodColor::parse_str("rgb(255 0 0 / 50%)").unwrap(),
+        FloodColor(cssparser::Color::RGBA(cssparser::RGBA::new(255, 0, 0, 128)))
+    );
+
+    assert_eq!(
+        FloodColor::parse_str("hsl(120deg 100% 50% / 0.25)").unwrap(),
+        FloodColor(cssparser::Color::RGBA(cssparser::RGBA::new(0, 255, 0, 64)))
+    );
+}
+
 // https://www.w3.org/TR/SVG/filters.html#FloodOpacityProperty
 make_property!(
     ComputedValues,
@@ -406,6 +443,28 @@ make_property!(
     newtype_parse: cssparser::Color,
 );
 
+#[cfg(test)]
+#[test]
+fn parses_lighting_color_with_modern_syntax() {
+    assert_eq!(
+        LightingColor::parse_str("rgb(255 0 0 / 50%)").unwrap(),
+        LightingColor(cssparser::Color::RGBA(cssparser::RGBA::new(255, 0, 0, 128)))
+    );
+
+    assert_eq!(
+        LightingColor::parse_str("rgb(0 255 0 / 0.5)").unwrap(),
+        LightingColor(cssparser::Color::RGBA(cssparser::RGBA::new(0, 255, 0, 128)))
+    );
+
+    assert_eq!(
+        LightingColor::parse_str("hsl(240 100% 50% / 100%)").unwrap(),
+        LightingColor(cssparser::Color::RGBA(cssparser::RGBA::new(0, 0, 255, 255)))
+    );
+
+    assert!(LightingColor::parse_str("rgb(255 0 0 /)").is_err());
+    assert!(LightingColor::parse_str("rgb(255, 0 0)").is_err());
+}
+
 make_property!(
     ComputedValues,
     Marker,
@@ -1,7 +1,25 @@
 //! Types for rectangles.
 
+/// Converts a coordinate to `i32`, saturating at the limits of `i32`, and turning NaN into 0.
+///
+/// Rust 1.45 and later do this for `as` casts, but in earlier versions, which we support,
+/// out-of-range casts are undefined behavior.
+#[inline]
+fn saturating_i32(v: f64) -> i32 {
+    if v.is_nan() {
+        0
+    } else if v <= f64::from(i32::min_value()) {
+        i32::min_value()
+    } else if v >= f64::from(i32::max_value()) {
+        i32::max_value()
+    } else {
+        v as i32
+    }
+}
+
 #[allow(clippy::module_inception)]
 mod rect {
+    use super::saturating_i32;
     use crate::float_eq_cairo::ApproxEqCairo;
     use core::ops::{Add, Range, Sub};
     use num_traits::Zero;
@@ -129,10 +147,10 @@ mod rect {
         #[inline]
         pub fn scale(self, x: f64, y: f64) -> Self {
             Self {
-                x0: (f64::from(self.x0) * x).floor() as i32,
-                y0: (f64::from(self.y0) * y).floor() as i32,
-                x1: (f64::from(self.x1) * x).ceil() as i32,
-                y1: (f64::from(self.y1) * y).ceil() as i32,
+                x0: saturating_i32((f64::from(self.x0) * x).floor()),
+                y0: saturating_i32((f64::from(self.y0) * y).floor()),
+                x1: saturating_i32((f64::from(self.x1) * x).ceil()),
+                y1: saturating_i32((f64::from(self.y1) * y).ceil()),
             }
         }
     }
@@ -161,10 +179,10 @@ impl From<Rect> for IRect {
     #[inline]
     fn from(r: Rect) -> Self {
         Self {
-            x0: r.x0.floor() as i32,
-            y0: r.y0.floor() as i32,
-            x1: r.x1.ceil() as i32,
-            y1: r.y1.ceil() as i32,
+            x0: saturating_i32(r.x0.floor()),
+            y0: saturating_i32(r.y0.floor()),
+            x1: saturating_i32(r.x1.ceil()),
+            y1: saturating_i32(r.y1.ceil()),
         }
     }
 }
@@ -211,10 +229,10 @@ impl From<cairo::Rectangle> for IRect {
     #[inline]
     fn from(r: cairo::Rectangle) -> Self {
         Self {
-            x0: r.x.floor() as i32,
-            y0: r.y.floor() as i32,
-            x1: (r.x + r.width).ceil() as i32,
-            y1: (r.y + r.height).ceil() as i32,
+            x0: saturating_i32(r.x.floor()),
+            y0: saturating_i32(r.y.floor()),
+            x1: saturating_i32((r.x + r.width).ceil()),
+            y1: saturating_i32((r.y + r.height).ceil()),
         }
     }
 }
@@ -230,3 +248,48 @@ impl From<IRect> for cairo::Rectangle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_to_irect_saturates() {
+        let huge = Rect::new(-1e20, -1e300, 1e20, std::f64::INFINITY);
+        assert_eq!(
+            IRect::from(huge),
+            IRect::new(
+                i32::min_value(),
+                i32::min_value(),
+                i32::max_value(),
+                i32::max_value()
+            )
+        );
+
+        let cairo_rect = cairo::Rectangle {
+            x: -1e20,
+            y: 0.5,
+            width: 2e20,
+            height: std::f64::NEG_INFINITY,
+        };
+        assert_eq!(
+            IRect::from(cairo_rect),
+            IRect::new(i32::min_value(), 0, i32::max_value(), i32::min_value())
+        );
+    }
+
+    #[test]
+    fn rect_to_irect_turns_nan_into_zero() {
+        let nan = Rect::new(std::f64::NAN, 1.5, 10.5, std::f64::NAN);
+        assert_eq!(IRect::from(nan), IRect::new(0, 1, 11, 0));
+    }
+
+    #[test]
+    fn irect_scale_saturates() {
+        let r = IRect::new(-10, -10, 10, 10);
+        assert_eq!(
+            r.scale(1e10, std::f64::NAN),
+            IRect::new(i32::min_value(), 0, i32::max_value(), 0)
+        );
+    }
+}
@@ -105,6 +105,49 @@ impl<'a> PixelRectangle<'a> {
     }
 }
 
+/// Returns the pixel at `(x, y)`, which may lie outside of `bounds`.
+///
+/// Pixels outside of `bounds` are computed according to `edge_mode`.  Unless `edge_mode` is
+/// `EdgeMode::None`, the `bounds` must not be empty.
+#[inline]
+pub fn get_pixel_edge(
+    surface: &SharedImageSurface,
+    bounds: IRect,
+    x: i32,
+    y: i32,
+    edge_mode: EdgeMode,
+) -> Pixel {
+    if bounds.contains(x, y) {
+        return surface.get_pixel(x as u32, y as u32);
+    }
+
+    match edge_mode {
+        EdgeMode::None => Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        },
+        EdgeMode::Duplicate => {
+            let x = clamp(x, bounds.x0, bounds.x1 - 1);
+            let y = clamp(y, bounds.y0, bounds.y1 - 1);
+            surface.get_pixel(x as u32, y as u32)
+        }
+        EdgeMode::Wrap => {
+            let wrap = |mut x, v| {
+                while x < 0 {
+                    x += v;
+                }
+                x % v
+            };
+
+            let x = bounds.x0 + wrap(x - bounds.x0, bounds.width());
+            let y = bounds.y0 + wrap(y - bounds.y0, bounds.height());
+            surface.get_pixel(x as u32, y as u32)
+        }
+    }
+}
+
 impl<'a> Iterator for Pixels<'a> {
     type Item = (u32, u32, Pixel);
 
@@ -144,41 +187,11 @@ impl<'a> Iterator for PixelRectangle<'a> {
             return None;
         }
 
-        let rv = {
-            let get_pixel = |x, y| {
-                if !self.bounds.contains(x, y) {
-                    match self.edge_mode {
-                        EdgeMode::None => Pixel {
-                            r: 0,
-                            g: 0,
-                            b: 0,
-                            a: 0,
-                        },
-                        EdgeMode::Duplicate => {
-                            let x = clamp(x, self.bounds.x0, self.bounds.x1 - 1);
-                            let y = clamp(y, self.bounds.y0, self.bounds.y1 - 1);
-                            self.surface.get_pixel(x as u32, y as u32)
-                        }
-                        EdgeMode::Wrap => {
-                            let wrap = |mut x, v| {
-                                while x < 0 {
-                                    x += v;
-                                }
-                                x % v
-                            };
-
-                            let x = self.bounds.x0 + wrap(x - self.bounds.x0, self.bounds.width());
-                            let y = self.bounds.y0 + wrap(y - self.bounds.y0, self.bounds.height());
-                            self.surface.get_pixel(x as u32, y as u32)
-                        }
-                    }
-                } else {
-                    self.surface.get_pixel(x as u32, y as u32)
-                }
-            };
-
-            Some((self.x, self.y, get_pixel(self.x, self.y)))
-        };
+        let rv = Some((
+            self.x,
+            self.y,
+            get_pixel_edge(self.surface, self.bounds, self.x, self.y, self.edge_mode),
+        ));
 
         if self.x + 1 == self.rectangle.x1 {
             self.x = self.rectangle.x0;
@@ -194,7 +207,8 @@ impl<'a> Iterator for PixelRectangle<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::surface_utils::shared_surface::SurfaceType;
+    use crate::surface_utils::shared_surface::{ExclusiveImageSurface, SurfaceType};
+    use crate::surface_utils::ImageSurfaceDataExt;
 
     #[test]
     fn pixels_count() {
@@ -244,4 +258,54 @@ mod tests {
             (16 * 16) as usize
         );
     }
+
+    #[test]
+    fn get_pixel_edge_outside_each_edge() {
+        // A 3×3 surface where each pixel encodes its coordinates.
+        let pixel = |x: i32, y: i32| Pixel {
+            r: x as u8,
+            g: y as u8,
+            b: 0,
+            a: 255,
+        };
+
+        let mut surface = ExclusiveImageSurface::new(3, 3, SurfaceType::SRgb).unwrap();
+        surface.modify(&mut |data, stride| {
+            for y in 0..3 {
+                for x in 0..3 {
+                    data.set_pixel(stride, pixel(x, y), x as u32, y as u32);
+                }
+            }
+        });
+        let surface = surface.share().unwrap();
+
+        let bounds = IRect::from_size(3, 3);
+        let get = |x, y, edge_mode| get_pixel_edge(&surface, bounds, x, y, edge_mode);
+
+        let transparent = Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        };
+
+        // (outside point, duplicated pixel, wrapped pixel)
+        let cases = [
+            ((-1, 1), pixel(0, 1), pixel(2, 1)),
+            ((3, 1), pixel(2, 1), pixel(0, 1)),
+            ((1, -1), pixel(1, 0), pixel(1, 2)),
+            ((1, 3), pixel(1, 2), pixel(1, 0)),
+        ];
+
+        for &((x, y), duplicate, wrap) in &cases {
+            assert_eq!(get(x, y, EdgeMode::None), transparent);
+            assert_eq!(get(x, y, EdgeMode::Duplicate), duplicate);
+            assert_eq!(get(x, y, EdgeMode::Wrap), wrap);
+        }
+
+        // Pixels inside the bounds are the same in every mode.
+        for &edge_mode in &[EdgeMode::None, EdgeMode::Duplicate, EdgeMode::Wrap] {
+            assert_eq!(get(1, 2, edge_mode), pixel(1, 2));
+        }
+    }
 }
@@ -4,6 +4,8 @@ use std::mem;
 use std::ops::DerefMut;
 use std::slice;
 
+use crate::rect::IRect;
+
 pub mod iterators;
 pub mod shared_surface;
 pub mod srgb;
@@ -63,16 +65,83 @@ pub enum EdgeMode {
     None,
 }
 
+/// Modes which specify how fractional offsets are applied when translating an image.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OffsetMode {
+    /// The offset is rounded to the nearest device pixel, so pixels are copied verbatim.
+    ///
+    /// This avoids blurring the image, but sub-pixel motion shows up as jitter.
+    Round,
+    /// The exact offset is used, and the image is resampled with bilinear interpolation.
+    ///
+    /// This is the default, since the SVG spec does not call for any rounding of `dx`/`dy`.
+    Bilinear,
+}
+
+impl Default for OffsetMode {
+    #[inline]
+    fn default() -> Self {
+        OffsetMode::Bilinear
+    }
+}
+
 /// Extension methods for `cairo::ImageSurfaceData`.
 pub trait ImageSurfaceDataExt: DerefMut<Target = [u8]> {
+    /// Returns the byte offset of the pixel at the given coordinates. Assumes the `ARgb32`
+    /// format.
+    ///
+    /// Returns `None` if the offset overflows or if the pixel does not fit in the data.
+    #[inline]
+    fn pixel_offset(&self, stride: usize, x: u32, y: u32) -> Option<usize> {
+        let offset = (y as usize)
+            .checked_mul(stride)?
+            .checked_add((x as usize).checked_mul(4)?)?;
+
+        if offset.checked_add(4)? <= self.len() {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
     /// Sets the pixel at the given coordinates. Assumes the `ARgb32` format.
+    ///
+    /// # Panics
+    /// Panics if the pixel is not contained within the surface data.
     #[inline]
     fn set_pixel(&mut self, stride: usize, pixel: Pixel, x: u32, y: u32) {
         let value = pixel.to_u32();
+        let offset = self
+            .pixel_offset(stride, x, y)
+            .expect("pixel coordinates out of bounds");
 
         #[allow(clippy::cast_ptr_alignment)]
         unsafe {
-            *(&mut self[y as usize * stride + x as usize * 4] as *mut u8 as *mut u32) = value;
+            *(&mut self[offset] as *mut u8 as *mut u32) = value;
+        }
+    }
+
+    /// Clears the pixels within `bounds` to transparent black. Assumes the `ARgb32` format.
+    ///
+    /// This is useful to prepare part of a surface for reuse as an output buffer.
+    ///
+    /// # Panics
+    /// Panics if `bounds` is not contained within the surface data.
+    #[inline]
+    fn clear_region(&mut self, stride: usize, bounds: IRect) {
+        if bounds.is_empty() {
+            return;
+        }
+
+        let row_start = bounds.x0 as usize * 4;
+        let row_end = bounds.x1 as usize * 4;
+
+        for y in bounds.y_range() {
+            let offset = y as usize * stride;
+
+            for byte in &mut self[offset + row_start..offset + row_end] {
+                *byte = 0;
+            }
         }
     }
 }
@@ -182,7 +251,173 @@ impl Pixel {
 
         Pixel { r, g, b, a }
     }
+
+    /// Combines two premultiplied pixels as `self * fa + below * fb`.
+    ///
+    /// `fa` and `fb` are the Porter-Duff fractions, scaled to 0..=255.
+    #[inline]
+    fn porter_duff(self, below: Pixel, fa: u8, fb: u8) -> Pixel {
+        let fa = u32::from(fa);
+        let fb = u32::from(fb);
+        let blend = |s: u8, d: u8| {
+            let x = (u32::from(s) * fa + u32::from(d) * fb + 127) / 255;
+            x.min(255) as u8
+        };
+
+        Pixel {
+            r: blend(self.r, below.r),
+            g: blend(self.g, below.g),
+            b: blend(self.b, below.b),
+            a: blend(self.a, below.a),
+        }
+    }
+
+    /// Porter-Duff `over`; both pixels must be premultiplied.
+    #[inline]
+    pub fn over(self, below: Pixel) -> Pixel {
+        self.porter_duff(below, 255, 255 - self.a)
+    }
+
+    /// Porter-Duff `in`; both pixels must be premultiplied.
+    #[inline]
+    pub fn in_(self, below: Pixel) -> Pixel {
+        self.porter_duff(below, below.a, 0)
+    }
+
+    /// Porter-Duff `out`; both pixels must be premultiplied.
+    #[inline]
+    pub fn out(self, below: Pixel) -> Pixel {
+        self.porter_duff(below, 255 - below.a, 0)
+    }
+
+    /// Porter-Duff `atop`; both pixels must be premultiplied.
+    #[inline]
+    pub fn atop(self, below: Pixel) -> Pixel {
+        self.porter_duff(below, below.a, 255 - self.a)
+    }
+
+    /// Porter-Duff `xor`; both pixels must be premultiplied.
+    #[inline]
+    pub fn xor(self, below: Pixel) -> Pixel {
+        self.porter_duff(below, 255 - below.a, 255 - self.a)
+    }
 }
 
 impl<'a> ImageSurfaceDataExt for cairo::ImageSurfaceData<'a> {}
 impl<'a> ImageSurfaceDataExt for &'a mut [u8] {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_region_only_touches_bounds() {
+        const WIDTH: u32 = 8;
+        const HEIGHT: u32 = 6;
+        const STRIDE: usize = WIDTH as usize * 4;
+
+        let mut data = vec![0xffu8; STRIDE * HEIGHT as usize];
+        let bounds = IRect::new(2, 1, 5, 4);
+
+        {
+            let mut slice: &mut [u8] = &mut data;
+            slice.clear_region(STRIDE, bounds);
+        }
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let offset = y as usize * STRIDE + x as usize * 4;
+                let pixel = &data[offset..offset + 4];
+
+                if bounds.contains(x as i32, y as i32) {
+                    assert_eq!(pixel, &[0, 0, 0, 0]);
+                } else {
+                    assert_eq!(pixel, &[0xff, 0xff, 0xff, 0xff]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clear_region_ignores_empty_bounds() {
+        let mut data = vec![0xffu8; 16];
+
+        {
+            let mut slice: &mut [u8] = &mut data;
+            slice.clear_region(8, IRect::new(1, 1, 1, 2));
+        }
+
+        assert!(data.iter().all(|&b| b == 0xff));
+    }
+
+    #[test]
+    fn pixel_offset_checks_bounds() {
+        // 2x2 pixels with a padded stride of 12 bytes.
+        let mut data = vec![0u8; 24];
+        let slice: &mut [u8] = &mut data;
+
+        assert_eq!(slice.pixel_offset(12, 0, 0), Some(0));
+        assert_eq!(slice.pixel_offset(12, 1, 1), Some(16));
+        assert_eq!(slice.pixel_offset(12, 0, 2), None);
+        assert_eq!(slice.pixel_offset(12, 5, 1), None);
+        assert_eq!(slice.pixel_offset(usize::MAX, 0, 2), None);
+    }
+
+    fn pixel(r: u8, g: u8, b: u8, a: u8) -> Pixel {
+        Pixel { r, g, b, a }
+    }
+
+    #[test]
+    fn porter_duff_over() {
+        let transparent = pixel(0, 0, 0, 0);
+        let red = pixel(255, 0, 0, 255);
+        let half_blue = pixel(0, 0, 128, 128);
+
+        assert_eq!(half_blue.over(red), pixel(127, 0, 128, 255));
+        assert_eq!(red.over(half_blue), red);
+        assert_eq!(transparent.over(half_blue), half_blue);
+        assert_eq!(half_blue.over(transparent), half_blue);
+    }
+
+    #[test]
+    fn porter_duff_in() {
+        let red = pixel(255, 0, 0, 255);
+        let half_green = pixel(0, 128, 0, 128);
+        let half_blue = pixel(0, 0, 128, 128);
+
+        assert_eq!(half_blue.in_(red), half_blue);
+        assert_eq!(half_blue.in_(half_green), pixel(0, 0, 64, 64));
+        assert_eq!(red.in_(pixel(0, 0, 0, 0)), pixel(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn porter_duff_out() {
+        let red = pixel(255, 0, 0, 255);
+        let blue = pixel(0, 0, 255, 255);
+        let half_green = pixel(0, 128, 0, 128);
+
+        assert_eq!(blue.out(half_green), pixel(0, 0, 127, 127));
+        assert_eq!(blue.out(red), pixel(0, 0, 0, 0));
+        assert_eq!(blue.out(pixel(0, 0, 0, 0)), blue);
+    }
+
+    #[test]
+    fn porter_duff_atop() {
+        let red = pixel(255, 0, 0, 255);
+        let half_blue = pixel(0, 0, 128, 128);
+
+        assert_eq!(half_blue.atop(red), pixel(127, 0, 128, 255));
+        assert_eq!(half_blue.atop(pixel(0, 0, 0, 0)), pixel(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn porter_duff_xor() {
+        let red = pixel(255, 0, 0, 255);
+        let blue = pixel(0, 0, 255, 255);
+        let half_blue = pixel(0, 0, 128, 128);
+
+        assert_eq!(blue.xor(red), pixel(0, 0, 0, 0));
+        assert_eq!(half_blue.xor(red), pixel(127, 0, 0, 127));
+        assert_eq!(half_blue.xor(pixel(0, 0, 0, 0)), half_blue);
+    }
+}
@@ -1,5 +1,6 @@
 //! Shared access to Cairo image surfaces.
 use std::cmp::min;
+use std::iter;
 use std::marker::PhantomData;
 use std::ptr::NonNull;
 use std::slice;
@@ -15,7 +16,7 @@ use crate::util::clamp;
 
 use super::{
     iterators::{PixelRectangle, Pixels},
-    AsCairoARGB, CairoARGB, EdgeMode, ImageSurfaceDataExt, Pixel,
+    AsCairoARGB, CairoARGB, EdgeMode, ImageSurfaceDataExt, OffsetMode, Pixel,
 };
 
 /// Types of pixel data in a `ImageSurface`.
@@ -369,6 +370,17 @@ impl ImageSurface<Shared> {
         Pixel::from_u32(value)
     }
 
+    /// Retrieves the pixel value at the given coordinates, or `None` if they are outside the
+    /// surface.
+    #[inline]
+    pub fn try_get_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x < self.width as u32 && y < self.height as u32 {
+            Some(self.get_pixel(x, y))
+        } else {
+            None
+        }
+    }
+
     /// Retrieves the pixel value by offset into the pixel data array.
     #[inline]
     pub fn get_pixel_by_offset(&self, offset: isize) -> Pixel {
@@ -524,6 +536,32 @@ impl ImageSurface<Shared> {
         SharedImageSurface::wrap(output_surface, self.surface_type)
     }
 
+    /// Returns a surface with the color values premultiplied by alpha.
+    ///
+    /// This is the inverse of [`unpremultiply`], for surfaces that hold unpremultiplied pixels.
+    ///
+    /// [`unpremultiply`]: #method.unpremultiply
+    pub fn premultiply(&self, bounds: IRect) -> Result<SharedImageSurface, cairo::Status> {
+        // Premultiplication doesn't affect the alpha channel.
+        if self.is_alpha_only() {
+            return Ok(self.clone());
+        }
+
+        let mut output_surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, self.width, self.height)?;
+
+        let stride = output_surface.get_stride() as usize;
+        {
+            let mut data = output_surface.get_data().unwrap();
+
+            for (x, y, pixel) in Pixels::within(self, bounds) {
+                data.set_pixel(stride, pixel.premultiply(), x, y);
+            }
+        }
+
+        SharedImageSurface::wrap(output_surface, self.surface_type)
+    }
+
     /// Converts the surface to the linear sRGB color space.
     #[inline]
     pub fn to_linear_rgb(&self, bounds: IRect) -> Result<SharedImageSurface, cairo::Status> {
@@ -1002,16 +1040,25 @@ impl ImageSurface<Shared> {
     }
 
     /// Offsets the image of the specified amount.
+    ///
+    /// The `mode` determines whether fractional offsets are rounded to the pixel grid or
+    /// applied exactly with bilinear sampling.
     #[inline]
     pub fn offset(
         &self,
         bounds: IRect,
         dx: f64,
         dy: f64,
+        mode: OffsetMode,
     ) -> Result<SharedImageSurface, cairo::Status> {
         let output_surface =
             cairo::ImageSurface::create(cairo::Format::ARgb32, self.width, self.height)?;
 
+        let (dx, dy) = match mode {
+            OffsetMode::Round => (dx.round(), dy.round()),
+            OffsetMode::Bilinear => (dx, dy),
+        };
+
         // output_bounds contains all pixels within bounds,
         // for which (x - ox) and (y - oy) also lie within bounds.
         if let Some(output_bounds) = bounds
@@ -1024,6 +1071,11 @@ impl ImageSurface<Shared> {
             cr.clip();
 
             self.set_as_source_surface(&cr, dx, dy);
+
+            if mode == OffsetMode::Bilinear {
+                cr.get_source().set_filter(cairo::Filter::Bilinear);
+            }
+
             cr.paint();
         }
 
@@ -1070,6 +1122,26 @@ impl ImageSurface<Shared> {
         SharedImageSurface::wrap(output_surface, image.surface_type)
     }
 
+    /// Returns a new surface of the same size, with the contents of this one
+    /// within `bounds` and transparent pixels everywhere else.
+    #[inline]
+    pub fn clip(&self, bounds: IRect) -> Result<SharedImageSurface, cairo::Status> {
+        let output_surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, self.width, self.height)?;
+
+        {
+            let cr = cairo::Context::new(&output_surface);
+            let r = cairo::Rectangle::from(bounds);
+            cr.rectangle(r.x, r.y, r.width, r.height);
+            cr.clip();
+
+            self.set_as_source_surface(&cr, 0f64, 0f64);
+            cr.paint();
+        }
+
+        SharedImageSurface::wrap(output_surface, self.surface_type)
+    }
+
     /// Creates a new surface with the size and content specified in `bounds`
     #[inline]
     pub fn tile(&self, bounds: IRect) -> Result<SharedImageSurface, cairo::Status> {
@@ -1154,6 +1226,9 @@ impl ImageSurface<Shared> {
     /// Each pixel of the resulting image is computed using the following formula:
     /// `res = k1*i1*i2 + k2*i1 + k3*i2 + k4`
     ///
+    /// As the Filter Effects spec requires, the formula is applied to premultiplied color
+    /// values, and the resulting color channels are clamped to the resulting alpha.
+    ///
     /// # Panics
     /// Panics if the two surface types are not compatible.
     #[inline]
@@ -1238,6 +1313,59 @@ impl<'a> Iterator for RowsMut<'a> {
     }
 }
 
+/// Computes the arithmetic composite of two pixels given as `[r, g, b, a]`.
+///
+/// The four channels go through the same straight-line code so that the compiler can
+/// vectorize it.  Returns `None` if the resulting pixel is fully transparent.
+#[inline]
+fn arithmetic_pixel(k: [f64; 4], p1: [u8; 4], p2: [u8; 4]) -> Option<[u8; 4]> {
+    let [k1, k2, k3, k4] = k;
+
+    let channel = |c: usize| {
+        let i1 = f64::from(p1[c]) / 255f64;
+        let i2 = f64::from(p2[c]) / 255f64;
+
+        k1 * i1 * i2 + k2 * i1 + k3 * i2 + k4
+    };
+
+    let o = [channel(0), channel(1), channel(2), channel(3)];
+
+    let oa = clamp(o[3], 0f64, 1f64);
+
+    if oa > 0f64 {
+        let to_u8 = |o: f64| ((clamp(o, 0f64, oa) * 255f64) + 0.5) as u8;
+
+        Some([
+            to_u8(o[0]),
+            to_u8(o[1]),
+            to_u8(o[2]),
+            ((oa * 255f64) + 0.5) as u8,
+        ])
+    } else {
+        None
+    }
+}
+
+/// Returns the `[r, g, b, a]` components of the pixels in the `x0..x1` columns of `row`.
+///
+/// Pixels past the end of the row, or all of them if there is no row, are transparent black.
+#[inline]
+fn row_components(
+    row: Option<&[CairoARGB]>,
+    x0: usize,
+    x1: usize,
+) -> impl Iterator<Item = [u8; 4]> + '_ {
+    let row = row.unwrap_or(&[]);
+    let end = min(x1, row.len());
+    let start = min(x0, end);
+
+    row[start..end]
+        .iter()
+        .map(|p| [p.r, p.g, p.b, p.a])
+        .chain(iter::repeat([0; 4]))
+        .take(x1 - x0)
+}
+
 /// Performs the arithmetic composite operation. Public for benchmarking.
 #[inline]
 pub fn composite_arithmetic(
@@ -1250,39 +1378,42 @@ pub fn composite_arithmetic(
     k3: f64,
     k4: f64,
 ) {
-    output_surface.modify(&mut |data, stride| {
-        for (x, y, pixel, pixel_2) in
-            Pixels::within(surface1, bounds).map(|(x, y, p)| (x, y, p, surface2.get_pixel(x, y)))
-        {
-            let i1a = f64::from(pixel.a) / 255f64;
-            let i2a = f64::from(pixel_2.a) / 255f64;
-            let oa = k1 * i1a * i2a + k2 * i1a + k3 * i2a + k4;
-            let oa = clamp(oa, 0f64, 1f64);
-
+    let k = [k1, k2, k3, k4];
+
+    let output_rect = IRect::from_size(output_surface.width(), output_surface.height());
+    let bounds = match bounds.intersection(&output_rect) {
+        Some(bounds) => bounds,
+        None => return,
+    };
+
+    let (x0, x1) = (bounds.x0 as usize, bounds.x1 as usize);
+
+    // The inputs may be smaller than the output; rows past their end are transparent.
+    let rows = surface1
+        .rows()
+        .map(Some)
+        .chain(iter::repeat(None))
+        .zip(surface2.rows().map(Some).chain(iter::repeat(None)))
+        .zip(output_surface.rows_mut())
+        .skip(bounds.y0 as usize)
+        .take((bounds.y1 - bounds.y0) as usize);
+
+    for ((row1, row2), output_row) in rows {
+        let pixels = row_components(row1, x0, x1)
+            .zip(row_components(row2, x0, x1))
+            .zip(&mut output_row[x0..x1]);
+
+        for ((p1, p2), dest) in pixels {
             // Contents of image surfaces are transparent by default, so if the resulting pixel is
             // transparent there's no need to do anything.
-            if oa > 0f64 {
-                let compute = |i1, i2| {
-                    let i1 = f64::from(i1) / 255f64;
-                    let i2 = f64::from(i2) / 255f64;
-
-                    let o = k1 * i1 * i2 + k2 * i1 + k3 * i2 + k4;
-                    let o = clamp(o, 0f64, oa);
-
-                    ((o * 255f64) + 0.5) as u8
-                };
-
-                let output_pixel = Pixel {
-                    r: compute(pixel.r, pixel_2.r),
-                    g: compute(pixel.g, pixel_2.g),
-                    b: compute(pixel.b, pixel_2.b),
-                    a: ((oa * 255f64) + 0.5) as u8,
-                };
-
-                data.set_pixel(stride, output_pixel, x, y);
+            if let Some([r, g, b, a]) = arithmetic_pixel(k, p1, p2) {
+                dest.r = r;
+                dest.g = g;
+                dest.b = b;
+                dest.a = a;
             }
         }
-    });
+    }
 }
 
 impl ImageSurface<Exclusive> {
@@ -1370,6 +1501,250 @@ mod tests {
     use super::*;
     use crate::surface_utils::iterators::Pixels;
 
+    #[test]
+    fn clone_and_scale_preserve_surface_type() {
+        let surface = SharedImageSurface::empty(4, 4, SurfaceType::LinearRgb).unwrap();
+
+        let cloned = surface.clone();
+        assert_eq!(cloned.surface_type(), SurfaceType::LinearRgb);
+
+        let (scaled, _) = surface.scale(IRect::from_size(4, 4), 0.5, 0.5).unwrap();
+        assert_eq!(scaled.surface_type(), SurfaceType::LinearRgb);
+    }
+
+    #[test]
+    fn arithmetic_composite_works_on_premultiplied_values() {
+        let surface_with_pixel = |pixel| {
+            let mut surface = ExclusiveImageSurface::new(1, 1, SurfaceType::SRgb).unwrap();
+            surface.modify(&mut |data, stride| data.set_pixel(stride, pixel, 0, 0));
+            surface.share().unwrap()
+        };
+
+        let pixel = |r, g, b, a| Pixel { r, g, b, a };
+
+        let bounds = IRect::from_size(1, 1);
+
+        let input_1 = surface_with_pixel(pixel(64, 0, 0, 64));
+        let input_2 = surface_with_pixel(pixel(0, 128, 0, 128));
+        let output = input_1
+            .compose_arithmetic(&input_2, bounds, 0.0, 1.0, 1.0, 0.0)
+            .unwrap();
+        assert_eq!(output.get_pixel(0, 0), pixel(64, 128, 0, 192));
+
+        let input_1 = surface_with_pixel(pixel(100, 50, 0, 128));
+        let input_2 = surface_with_pixel(pixel(0, 60, 60, 102));
+        let output = input_1
+            .compose_arithmetic(&input_2, bounds, 0.5, 0.25, 0.5, 0.0)
+            .unwrap();
+        assert_eq!(output.get_pixel(0, 0), pixel(25, 48, 30, 109));
+    }
+
+    #[test]
+    fn arithmetic_composite_handles_inputs_of_different_sizes() {
+        let opaque = |width, height, pixel: Pixel| {
+            let mut surface = ExclusiveImageSurface::new(width, height, SurfaceType::SRgb).unwrap();
+            for row in surface.rows_mut() {
+                for p in row.iter_mut() {
+                    p.r = pixel.r;
+                    p.g = pixel.g;
+                    p.b = pixel.b;
+                    p.a = pixel.a;
+                }
+            }
+            surface.share().unwrap()
+        };
+
+        let red = Pixel {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let green = Pixel {
+            r: 0,
+            g: 255,
+            b: 0,
+            a: 255,
+        };
+
+        let large = opaque(4, 4, red);
+        let small = opaque(2, 2, green);
+
+        // result = in2, so only the area covered by the smaller input is opaque.
+        let output = large
+            .compose_arithmetic(&small, IRect::from_size(4, 4), 0.0, 0.0, 1.0, 0.0)
+            .unwrap();
+
+        for (x, y, p) in Pixels::new(&output) {
+            if x < 2 && y < 2 {
+                assert_eq!(p, green);
+            } else {
+                assert_eq!(p.a, 0);
+            }
+        }
+
+        // The output has the size of the first input; bounds past it are ignored.
+        let output = small
+            .compose_arithmetic(&large, IRect::from_size(4, 4), 0.0, 1.0, 0.0, 0.0)
+            .unwrap();
+
+        assert_eq!((output.width(), output.height()), (2, 2));
+        assert!(Pixels::new(&output).all(|(_, _, p)| p == green));
+    }
+
+    // The straightforward per-channel implementation that composite_arithmetic() must match.
+    fn composite_arithmetic_scalar(
+        surface1: &SharedImageSurface,
+        surface2: &SharedImageSurface,
+        output_surface: &mut ExclusiveImageSurface,
+        bounds: IRect,
+        k1: f64,
+        k2: f64,
+        k3: f64,
+        k4: f64,
+    ) {
+        output_surface.modify(&mut |data, stride| {
+            for (x, y, pixel, pixel_2) in Pixels::within(surface1, bounds)
+                .map(|(x, y, p)| (x, y, p, surface2.get_pixel(x, y)))
+            {
+                let i1a = f64::from(pixel.a) / 255f64;
+                let i2a = f64::from(pixel_2.a) / 255f64;
+                let oa = k1 * i1a * i2a + k2 * i1a + k3 * i2a + k4;
+                let oa = clamp(oa, 0f64, 1f64);
+
+                if oa > 0f64 {
+                    let compute = |i1, i2| {
+                        let i1 = f64::from(i1) / 255f64;
+                        let i2 = f64::from(i2) / 255f64;
+
+                        let o = k1 * i1 * i2 + k2 * i1 + k3 * i2 + k4;
+                        let o = clamp(o, 0f64, oa);
+
+                        ((o * 255f64) + 0.5) as u8
+                    };
+
+                    let output_pixel = Pixel {
+                        r: compute(pixel.r, pixel_2.r),
+                        g: compute(pixel.g, pixel_2.g),
+                        b: compute(pixel.b, pixel_2.b),
+                        a: ((oa * 255f64) + 0.5) as u8,
+                    };
+
+                    data.set_pixel(stride, output_pixel, x, y);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn arithmetic_composite_matches_scalar_version() {
+        const SIDE: i32 = 32;
+
+        let surface_with_data = |seed: u32| {
+            let mut surface = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+
+            // Fill the surface with premultiplied pseudo-random pixels.
+            let mut state = seed;
+            for row in surface.rows_mut() {
+                for p in row.iter_mut() {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    let bytes = state.to_be_bytes();
+
+                    p.a = bytes[0];
+                    p.r = bytes[1].min(p.a);
+                    p.g = bytes[2].min(p.a);
+                    p.b = bytes[3].min(p.a);
+                }
+            }
+
+            surface.share().unwrap()
+        };
+
+        let input_1 = surface_with_data(1);
+        let input_2 = surface_with_data(2);
+
+        let bounds = IRect::new(3, 5, 29, 30);
+
+        for &(k1, k2, k3, k4) in &[
+            (0.0, 1.0, 1.0, 0.0),
+            (0.5, 0.25, 0.5, 0.0),
+            (1.0, -0.5, 0.75, 0.1),
+            (-1.0, 2.0, -0.3, -0.2),
+            (0.0, 0.0, 0.0, 1.0),
+        ] {
+            let mut output = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+            composite_arithmetic(&input_1, &input_2, &mut output, bounds, k1, k2, k3, k4);
+            let output = output.share().unwrap();
+
+            let mut expected = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+            composite_arithmetic_scalar(&input_1, &input_2, &mut expected, bounds, k1, k2, k3, k4);
+            let expected = expected.share().unwrap();
+
+            for (x, y, p) in Pixels::new(&output) {
+                assert_eq!(p, expected.get_pixel(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn premultiply_round_trips_unpremultiply() {
+        const SIDE: i32 = 16;
+
+        let mut surface = ExclusiveImageSurface::new(SIDE, SIDE, SurfaceType::SRgb).unwrap();
+        surface.modify(&mut |data, stride| {
+            for y in 0..SIDE as u32 {
+                for x in 0..SIDE as u32 {
+                    let a = (x * 16 + y) as u8;
+                    let pixel = Pixel {
+                        r: (x * 17) as u8,
+                        g: (y * 17) as u8,
+                        b: 255 - (x * 17) as u8,
+                        a,
+                    }
+                    .premultiply();
+
+                    data.set_pixel(stride, pixel, x, y);
+                }
+            }
+        });
+        let surface = surface.share().unwrap();
+
+        let bounds = IRect::from_size(SIDE, SIDE);
+        let round_trip = surface
+            .unpremultiply(bounds)
+            .unwrap()
+            .premultiply(bounds)
+            .unwrap();
+
+        let close = |a: u8, b: u8| (i32::from(a) - i32::from(b)).abs() <= 1;
+
+        for (x, y, p) in Pixels::new(&surface) {
+            let q = round_trip.get_pixel(x, y);
+
+            assert_eq!(p.a, q.a);
+            assert!(
+                close(p.r, q.r) && close(p.g, q.g) && close(p.b, q.b),
+                "{:?} became {:?}",
+                p,
+                q
+            );
+        }
+    }
+
+    #[test]
+    fn try_get_pixel_checks_bounds() {
+        let surface = ExclusiveImageSurface::new(4, 2, SurfaceType::SRgb)
+            .unwrap()
+            .share()
+            .unwrap();
+
+        assert!(surface.try_get_pixel(0, 0).is_some());
+        assert!(surface.try_get_pixel(3, 1).is_some());
+        assert_eq!(surface.try_get_pixel(4, 0), None);
+        assert_eq!(surface.try_get_pixel(0, 2), None);
+        assert_eq!(surface.try_get_pixel(u32::MAX, u32::MAX), None);
+    }
+
     #[test]
     fn test_extract_alpha() {
         const WIDTH: i32 = 32;
@@ -1408,4 +1783,64 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    #[should_panic]
+    fn wrap_rejects_non_argb32_surface() {
+        // Pixel access assumes the ARgb32 layout; anything else would silently swap channels.
+        let surface = cairo::ImageSurface::create(cairo::Format::Rgb24, 8, 8).unwrap();
+        let _ = SharedImageSurface::wrap(surface, SurfaceType::SRgb);
+    }
+
+    fn surface_with_opaque_pixel(x: u32, y: u32) -> SharedImageSurface {
+        let mut surface = ExclusiveImageSurface::new(8, 8, SurfaceType::SRgb).unwrap();
+
+        surface.modify(&mut |data, stride| {
+            let opaque = Pixel {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 255,
+            };
+            data.set_pixel(stride, opaque, x, y);
+        });
+
+        surface.share().unwrap()
+    }
+
+    #[test]
+    fn offset_round_snaps_to_pixel_grid() {
+        let surface = surface_with_opaque_pixel(2, 2);
+        let bounds = IRect::from_size(8, 8);
+
+        let offset = surface.offset(bounds, 1.4, 0.0, OffsetMode::Round).unwrap();
+
+        assert_eq!(offset.get_pixel(2, 2).a, 0);
+        assert_eq!(offset.get_pixel(3, 2).a, 255);
+        assert_eq!(offset.get_pixel(4, 2).a, 0);
+
+        let offset = surface.offset(bounds, 0.0, 1.6, OffsetMode::Round).unwrap();
+
+        assert_eq!(offset.get_pixel(2, 3).a, 0);
+        assert_eq!(offset.get_pixel(2, 4).a, 255);
+    }
+
+    #[test]
+    fn offset_bilinear_spreads_subpixel_offset() {
+        let surface = surface_with_opaque_pixel(2, 2);
+        let bounds = IRect::from_size(8, 8);
+
+        let offset = surface
+            .offset(bounds, 1.5, 0.0, OffsetMode::Bilinear)
+            .unwrap();
+
+        // The pixel lands halfway between x=3 and x=4.
+        let left = offset.get_pixel(3, 2).a;
+        let right = offset.get_pixel(4, 2).a;
+
+        assert!(left > 0 && left < 255);
+        assert!(right > 0 && right < 255);
+        assert_eq!(offset.get_pixel(2, 2).a, 0);
+        assert_eq!(offset.get_pixel(5, 2).a, 0);
+    }
 }
@@ -9,7 +9,7 @@ use cssparser::{Parser, Token};
 use crate::angle::Angle;
 use crate::error::*;
 use crate::parsers::{optional_comma, Parse};
-use crate::rect::Rect;
+use crate::rect::{IRect, Rect};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Transform {
@@ -194,6 +194,12 @@ impl Transform {
             y1: ymax,
         }
     }
+
+    /// Transforms an integer rectangle, rounding outwards so the result covers all of
+    /// the transformed rectangle.
+    pub fn transform_irect(&self, rect: &IRect) -> IRect {
+        IRect::from(self.transform_rect(&Rect::from(*rect)))
+    }
 }
 
 impl Default for Transform {
@@ -600,4 +606,26 @@ mod tests {
     fn parses_empty() {
         assert_transform_eq(&parse_transform("").unwrap(), &Transform::identity());
     }
+
+    #[test]
+    fn transforms_irect() {
+        let rect = IRect::new(1, 2, 5, 6);
+
+        assert_eq!(
+            Transform::new_translate(10.0, -2.5).transform_irect(&rect),
+            IRect::new(11, -1, 15, 4)
+        );
+
+        assert_eq!(
+            Transform::new_scale(1.5, 0.5).transform_irect(&rect),
+            IRect::new(1, 1, 8, 3)
+        );
+
+        assert_eq!(
+            Transform::new_translate(1.0, 1.0)
+                .pre_scale(-2.0, 2.0)
+                .transform_irect(&rect),
+            IRect::new(-9, 5, -1, 13)
+        );
+    }
 }
@@ -11,11 +11,13 @@ use std::collections::HashMap;
 use std::rc::{Rc, Weak};
 use std::str;
 use std::string::ToString;
+use url::Url;
 use xml5ever::tendril::format_tendril;
 use xml5ever::tokenizer::{TagKind, Token, TokenSink, XmlTokenizer, XmlTokenizerOpts};
 
-use crate::allowed_url::AllowedUrl;
+use crate::allowed_url::{AllowedUrl, AllowedUrlError};
 use crate::document::{Document, DocumentBuilder};
+use crate::element::Element;
 use crate::error::LoadingError;
 use crate::io::{self, get_input_stream_for_loading};
 use crate::limits::MAX_LOADED_ELEMENTS;
@@ -101,6 +103,9 @@ struct XmlStateInner {
     context_stack: Vec<Context>,
     current_node: Option<Node>,
 
+    // The `xml:base` of each element being processed, parallel to the context_stack
+    xml_base_stack: Vec<Option<Url>>,
+
     entities: HashMap<String, XmlEntityPtr>,
 }
 
@@ -108,6 +113,9 @@ pub struct XmlState {
     inner: RefCell<XmlStateInner>,
 
     unlimited_size: bool,
+
+    /// Whether unknown elements in the SVG namespace are a fatal error.
+    strict_elements: bool,
 }
 
 /// Errors returned from XmlState::acquire()
@@ -127,10 +135,22 @@ impl XmlStateInner {
         // We can unwrap since the stack is never empty
         self.context_stack.last().unwrap().clone()
     }
+
+    // The base URL given by the innermost `xml:base` attribute, if any
+    fn xml_base(&self) -> Option<&Url> {
+        self.xml_base_stack
+            .iter()
+            .rev()
+            .find_map(|base| base.as_ref())
+    }
 }
 
 impl XmlState {
-    fn new(document_builder: DocumentBuilder, unlimited_size: bool) -> XmlState {
+    fn new(
+        document_builder: DocumentBuilder,
+        unlimited_size: bool,
+        strict_elements: bool,
+    ) -> XmlState {
         XmlState {
             inner: RefCell::new(XmlStateInner {
                 weak: None,
@@ -138,10 +158,12 @@ impl XmlState {
                 num_loaded_elements: 0,
                 context_stack: vec![Context::Start],
                 current_node: None,
+                xml_base_stack: Vec::new(),
                 entities: HashMap::new(),
             }),
 
             unlimited_size,
+            strict_elements,
         }
     }
 
@@ -177,6 +199,11 @@ impl XmlState {
 
         self.inner.borrow_mut().num_loaded_elements += 1;
 
+        // Pushed before processing the element, since the xml:base of an
+        // <xi:include> applies to its own href.
+        let xml_base = self.parse_xml_base(pbag);
+        self.inner.borrow_mut().xml_base_stack.push(xml_base);
+
         let new_context = match context {
             Context::Start => self.element_creation_start_element(&name, pbag),
             Context::ElementCreation => self.element_creation_start_element(&name, pbag),
@@ -216,7 +243,9 @@ impl XmlState {
         }
 
         // We can unwrap since start_element() always adds a context to the stack
-        self.inner.borrow_mut().context_stack.pop().unwrap();
+        let mut inner = self.inner.borrow_mut();
+        inner.context_stack.pop().unwrap();
+        inner.xml_base_stack.pop().unwrap();
     }
 
     pub fn characters(&self, text: &str) {
@@ -290,10 +319,15 @@ impl XmlState {
     }
 
     pub fn error(&self, e: LoadingError) {
-        self.inner
-            .borrow_mut()
-            .context_stack
-            .push(Context::FatalError(e));
+        let mut inner = self.inner.borrow_mut();
+
+        // Keep the first fatal error; later ones, like libxml2 complaining that the parser
+        // was stopped, are just consequences of it.
+        if let Context::FatalError(_) = inner.context() {
+            return;
+        }
+
+        inner.context_stack.push(Context::FatalError(e));
     }
 
     pub fn entity_lookup(&self, entity_name: &str) -> Option<XmlEntityPtr> {
@@ -312,9 +346,48 @@ impl XmlState {
         }
     }
 
+    fn parse_xml_base(&self, pbag: &PropertyBag) -> Option<Url> {
+        let value = pbag
+            .iter()
+            .find(|(attr, _)| attr.expanded() == expanded_name!(xml "base"))
+            .map(|(_, value)| value)?;
+
+        let inner = self.inner.borrow();
+        let base = inner
+            .xml_base()
+            .or_else(|| inner.document_builder.as_ref().unwrap().base_url());
+
+        Url::options()
+            .base_url(base)
+            .parse(value)
+            .map_err(|e| rsvg_log!("invalid xml:base \"{}\": {}", value, e))
+            .ok()
+    }
+
+    // Resolves an href against the innermost xml:base.  Whether the resulting
+    // URL can be loaded is still decided with respect to the document's base URL.
+    fn resolve_href(&self, href: &str) -> Result<AllowedUrl, AllowedUrlError> {
+        let inner = self.inner.borrow();
+        let document_builder = inner.document_builder.as_ref().unwrap();
+
+        match inner.xml_base() {
+            Some(base) => {
+                let url = base.join(href).map_err(AllowedUrlError::HrefParseError)?;
+                document_builder.resolve_href(url.as_str())
+            }
+
+            None => document_builder.resolve_href(href),
+        }
+    }
+
     fn element_creation_start_element(&self, name: &QualName, pbag: &PropertyBag) -> Context {
         if name.expanded() == xinclude_name!("include") {
             self.xinclude_start_element(name, pbag)
+        } else if self.strict_elements && name.ns == ns!(svg) && !Element::is_known_name(name) {
+            Context::FatalError(LoadingError::XmlParseError(format!(
+                "unknown element <{}>",
+                name.local
+            )))
         } else {
             let mut inner = self.inner.borrow_mut();
 
@@ -365,6 +438,14 @@ impl XmlState {
             .unwrap_or(StyleType::TextCss);
 
         if style_type == StyleType::TextCss {
+            // CSS is global to the document, so we parse it anyway, but note
+            // that the document is probably not what the author intended.
+            if !has_svg_parent(current_node) {
+                rsvg_log!(
+                    "<style> element is not a child of an SVG element; applying its CSS anyway"
+                );
+            }
+
             let stylesheet_text = current_node
                 .children()
                 .map(|child| {
@@ -475,19 +556,12 @@ impl XmlState {
         encoding: Option<&str>,
     ) -> Result<(), AcquireError> {
         if let Some(href) = href {
-            let aurl = self
-                .inner
-                .borrow()
-                .document_builder
-                .as_ref()
-                .unwrap()
-                .resolve_href(href)
-                .map_err(|e| {
-                    // FIXME: should AlloweUrlError::HrefParseError be a fatal error,
-                    // not a resource error?
-                    rsvg_log!("could not acquire \"{}\": {}", href, e);
-                    AcquireError::ResourceError
-                })?;
+            let aurl = self.resolve_href(href).map_err(|e| {
+                // FIXME: should AlloweUrlError::HrefParseError be a fatal error,
+                // not a resource error?
+                rsvg_log!("could not acquire \"{}\": {}", href, e);
+                AcquireError::ResourceError
+            })?;
 
             // https://www.w3.org/TR/xinclude/#include_element
             //
@@ -520,7 +594,11 @@ impl XmlState {
             AcquireError::ResourceError
         })?;
 
-        let encoding = encoding.unwrap_or("utf-8");
+        // A byte order mark takes precedence over the encoding attribute.
+        let (encoding, data) = match sniff_bom(&binary.data) {
+            Some((encoding, bom_len)) => (encoding, &binary.data[bom_len..]),
+            None => (encoding.unwrap_or("utf-8"), &binary.data[..]),
+        };
 
         let encoder = encoding_from_whatwg_label(encoding).ok_or_else(|| {
             AcquireError::FatalError(format!(
@@ -529,14 +607,12 @@ impl XmlState {
             ))
         })?;
 
-        let utf8_data = encoder
-            .decode(&binary.data, DecoderTrap::Strict)
-            .map_err(|e| {
-                AcquireError::FatalError(format!(
-                    "could not convert contents of \"{}\" from character encoding \"{}\": {}",
-                    aurl, encoding, e
-                ))
-            })?;
+        let utf8_data = encoder.decode(data, DecoderTrap::Strict).map_err(|e| {
+            AcquireError::FatalError(format!(
+                "could not convert contents of \"{}\" from character encoding \"{}\": {}",
+                aurl, encoding, e
+            ))
+        })?;
 
         self.element_creation_characters(&utf8_data);
         Ok(())
@@ -580,9 +656,12 @@ impl XmlState {
             .unwrap()
             .upgrade()
             .unwrap();
-        Xml2Parser::from_stream(strong, self.unlimited_size, stream, cancellable)
-            .and_then(|parser| parser.parse())
-            .and_then(|_: ()| self.check_last_error())
+        let result = Xml2Parser::from_stream(strong, self.unlimited_size, stream, cancellable)
+            .and_then(|parser| parser.parse());
+
+        // A fatal error from our handlers stops the parser; report it instead of libxml2's
+        // own error about the parser being stopped.
+        self.check_last_error().and(result)
     }
 
     fn unsupported_xinclude_start_element(&self, _name: &QualName) -> Context {
@@ -647,6 +726,28 @@ impl TokenSink for ProcessingInstructionSink {
     }
 }
 
+// Whether the node's parent is an element in the SVG namespace.
+fn has_svg_parent(node: &Node) -> bool {
+    node.parent()
+        .map(|parent| parent.is_element() && parent.borrow_element().element_name().ns == ns!(svg))
+        .unwrap_or(false)
+}
+
+/// Detects a byte order mark at the start of `data`.
+///
+/// Returns the label of the encoding that it indicates, and the length of the mark.
+fn sniff_bom(data: &[u8]) -> Option<(&'static str, usize)> {
+    if data.starts_with(b"\xef\xbb\xbf") {
+        Some(("utf-8", 3))
+    } else if data.starts_with(b"\xff\xfe") {
+        Some(("utf-16le", 2))
+    } else if data.starts_with(b"\xfe\xff") {
+        Some(("utf-16be", 2))
+    } else {
+        None
+    }
+}
+
 // https://www.w3.org/TR/xml-stylesheet/
 //
 // The syntax for the xml-stylesheet processing instruction we support
@@ -683,10 +784,15 @@ fn parse_xml_stylesheet_processing_instruction(data: &str) -> Result<Vec<(String
 pub fn xml_load_from_possibly_compressed_stream(
     document_builder: DocumentBuilder,
     unlimited_size: bool,
+    strict_elements: bool,
     stream: &gio::InputStream,
     cancellable: Option<&gio::Cancellable>,
 ) -> Result<Document, LoadingError> {
-    let state = Rc::new(XmlState::new(document_builder, unlimited_size));
+    let state = Rc::new(XmlState::new(
+        document_builder,
+        unlimited_size,
+        strict_elements,
+    ));
 
     state.inner.borrow_mut().weak = Some(Rc::downgrade(&state));
 
@@ -699,6 +805,11 @@ pub fn xml_load_from_possibly_compressed_stream(
 mod tests {
     use super::*;
 
+    use crate::document::{load_document, load_document_with_options};
+    use crate::handle::LoadOptions;
+    use crate::length::*;
+    use crate::property_defs::StrokeWidth;
+
     #[test]
     fn parses_processing_instruction_data() {
         let mut r =
@@ -713,4 +824,143 @@ mod tests {
             ]
         );
     }
+
+    fn text_content(document: &Document, id: &str) -> String {
+        document
+            .lookup_node_by_id(id)
+            .unwrap()
+            .children()
+            .filter(|c| c.is_chars())
+            .map(|c| c.borrow_chars().get_string())
+            .collect()
+    }
+
+    #[test]
+    fn sniffs_byte_order_marks() {
+        assert_eq!(sniff_bom(b"\xef\xbb\xbfabc"), Some(("utf-8", 3)));
+        assert_eq!(sniff_bom(b"\xff\xfea\x00"), Some(("utf-16le", 2)));
+        assert_eq!(sniff_bom(b"\xfe\xff\x00a"), Some(("utf-16be", 2)));
+        assert_eq!(sniff_bom(b"abc"), None);
+        assert_eq!(sniff_bom(b""), None);
+    }
+
+    #[test]
+    fn xinclude_text_with_bom_overrides_encoding() {
+        // "h\u{e9}llo" with a UTF-8 BOM, and with a UTF-16LE BOM
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xi="http://www.w3.org/2001/XInclude">
+  <text id="utf8"><xi:include href="data:text/plain;base64,77u/aMOpbGxv" parse="text"/></text>
+  <text id="utf16le"><xi:include href="data:text/plain;base64,//5oAOkAbABsAG8A"
+                                 parse="text" encoding="iso-8859-1"/></text>
+</svg>
+"#,
+        );
+
+        assert_eq!(text_content(&document, "utf8"), "h\u{e9}llo");
+        assert_eq!(text_content(&document, "utf16le"), "h\u{e9}llo");
+    }
+
+    #[test]
+    fn style_outside_svg_element_still_applies() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:x="urn:example">
+  <x:foo>
+    <style id="style">rect { stroke-width: 7px; }</style>
+  </x:foo>
+  <rect id="rect" width="10" height="10"/>
+</svg>
+"#,
+        );
+
+        let style = document.lookup_node_by_id("style").unwrap();
+        assert!(!has_svg_parent(&style));
+
+        let rect = document.lookup_node_by_id("rect").unwrap();
+        assert!(has_svg_parent(&rect));
+        assert_eq!(
+            rect.borrow_element().get_computed_values().stroke_width(),
+            StrokeWidth(Length::<Both>::new(7.0, LengthUnit::Px))
+        );
+    }
+
+    #[test]
+    fn strict_elements_rejects_unknown_svg_elements() {
+        let input = br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:x="urn:example">
+  <x:foo/>
+  <rekt id="typo" width="10" height="10"/>
+  <rect width="10" height="10"/>
+</svg>
+"#;
+
+        let document = load_document_with_options(input, &LoadOptions::new(None)).unwrap();
+        let typo = document.lookup_node_by_id("typo").unwrap();
+        assert!(is_element_of_type!(typo, NonRendering));
+
+        match load_document_with_options(input, &LoadOptions::new(None).with_strict_elements(true))
+        {
+            Err(LoadingError::XmlParseError(msg)) => assert_eq!(msg, "unknown element <rekt>"),
+            _ => panic!("unknown element should be a loading error in strict mode"),
+        }
+    }
+
+    #[test]
+    fn strict_elements_accepts_known_elements() {
+        let input = br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:x="urn:example">
+  <x:foo/>
+  <rect width="10" height="10"/>
+</svg>
+"#;
+
+        assert!(load_document_with_options(
+            input,
+            &LoadOptions::new(None).with_strict_elements(true)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn records_attribute_errors() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <rect width="10" height="10"/>
+  <rect width="10" height="10" transform="foo"/>
+</svg>
+"#,
+        );
+
+        let diagnostics = document.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].element_name.local, local_name!("rect"));
+        assert_eq!(
+            diagnostics[0].error.attr.expanded(),
+            expanded_name!("", "transform")
+        );
+    }
+
+    #[test]
+    fn cdata_in_style_is_preserved() {
+        let document = load_document(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg">
+  <style><![CDATA[
+    /* fish & chips */
+    svg > rect { stroke-width: 4px; }
+    g > rect { stroke-width: 8px; }
+  ]]></style>
+  <rect id="rect" width="10" height="10"/>
+</svg>
+"#,
+        );
+
+        let rect = document.lookup_node_by_id("rect").unwrap();
+        assert_eq!(
+            rect.borrow_element().get_computed_values().stroke_width(),
+            StrokeWidth(Length::<Both>::new(4.0, LengthUnit::Px))
+        );
+    }
 }
@@ -4,6 +4,7 @@
 #include "config.h"
 
 #include <stdio.h>
+#include <string.h>
 #include <glib.h>
 #include <cairo.h>
 
@@ -959,6 +960,41 @@ get_intrinsic_dimensions (void)
     g_object_unref (handle);
 }
 
+static void
+get_diagnostics (void)
+{
+    char *filename = get_test_filename ("example.svg");
+    GError *error = NULL;
+
+    RsvgHandle *handle = rsvg_handle_new_from_file (filename, &error);
+    g_free (filename);
+
+    g_assert_nonnull (handle);
+    g_assert_no_error (error);
+
+    g_assert_null (rsvg_handle_get_diagnostics (handle));
+
+    g_object_unref (handle);
+
+    const char *data =
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">\n"
+        "  <rect width=\"10\" height=\"10\" transform=\"foo\"/>\n"
+        "</svg>\n";
+
+    handle = rsvg_handle_new_from_data ((const guint8 *) data, strlen (data), &error);
+    g_assert_nonnull (handle);
+    g_assert_no_error (error);
+
+    char *diagnostics = rsvg_handle_get_diagnostics (handle);
+    g_assert_nonnull (diagnostics);
+    g_assert_nonnull (strstr (diagnostics, "<rect>"));
+    g_assert_nonnull (strstr (diagnostics, "transform"));
+    g_assert_null (strchr (diagnostics, '\n'));
+    g_free (diagnostics);
+
+    g_object_unref (handle);
+}
+
 static void
 render_document (void)
 {
@@ -1438,6 +1474,7 @@ main (int argc, char **argv)
     g_test_add_func ("/api/can_draw_to_non_image_surface", can_draw_to_non_image_surface);
     g_test_add_func ("/api/render_cairo_sub", render_cairo_sub);
     g_test_add_func ("/api/get_intrinsic_dimensions", get_intrinsic_dimensions);
+    g_test_add_func ("/api/get_diagnostics", get_diagnostics);
     g_test_add_func ("/api/render_document", render_document);
     g_test_add_func ("/api/get_geometry_for_layer", get_geometry_for_layer);
     g_test_add_func ("/api/render_layer", render_layer);